@@ -1,88 +1,296 @@
+mod best;
+mod par;
+
+pub use self::best::{best_anagrams, AnagramScore};
+pub use self::par::par_anagrams;
+use crate::budget::Budget;
 use crate::lexi::Entry;
 use crate::lexi::FilterBuilder;
+use crate::lexi::LengthRange;
 use crate::lexi::Lexicon;
 use crate::lexi::SortedLetters;
+use crate::parallel;
+use crate::AnagramOpts;
 use itertools::Itertools;
-use std::cmp::Reverse;
+use owo_colors::OwoColorize;
+use std::cmp::{Ordering, Reverse};
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AnagramError {
+    #[error("{0:?} is not in the lexicon")]
+    NotAWord(String),
+}
+
+/// Limits on the anagrams to look for.
+#[derive(Debug, Clone, Default)]
+pub struct AnagramOptions {
+    /// Use the lexicon's phrases as well as its single words.
+    pub phrases: bool,
+    /// The most words in an anagram, counting the required words.
+    pub max_words: Option<usize>,
+    /// The fewest letters in each word, apart from the required words.
+    pub min_word_len: usize,
+    /// Words that every anagram must contain.
+    pub required: Vec<String>,
+    /// Words that no anagram may contain.
+    pub banned: Vec<String>,
+    /// Allow near misses that leave up to this many letters unused.
+    pub max_leftover: usize,
+    /// Give up after trying this many words.
+    pub max_nodes: Option<usize>,
+    /// Give up after this long.
+    pub time_limit: Option<Duration>,
+}
+
+/// One word of an anagram, and the letters that blanks stood for in it.
+#[derive(Debug, Clone)]
+pub struct AnagramWord<'a> {
+    pub entry: &'a Entry<'a>,
+    pub blanks: SortedLetters,
+}
 
 pub struct Anagrams<'a> {
-    entries: Vec<&'a Entry<'a>>,
+    search: Search<'a>,
     stack: Vec<AnagramFrame<'a>>,
 }
 
-#[derive(Debug)]
+/// The words that anagrams can use, and the limits on them, for both the
+/// depth-first and the best-first searches.
+#[derive(Clone)]
+struct Search<'a> {
+    lexi: &'a Lexicon<'a>,
+    entries: Vec<&'a Entry<'a>>,
+    /// The entries short enough to be left over from a near miss.
+    short: Vec<&'a Entry<'a>>,
+    max_words: usize,
+    min_word_len: usize,
+    max_leftover: usize,
+    budget: Arc<Budget>,
+}
+
+#[derive(Debug, Clone)]
 pub struct AnagramFrame<'a> {
     letters: SortedLetters,
     pos: usize,
-    partial_result: Vec<&'a Entry<'a>>,
+    /// The frame tries the words from `pos` up to here.
+    end: usize,
+    partial_result: Vec<AnagramWord<'a>>,
     is_virgin: bool,
 }
 
+/// Finds the ways to split the letters into words from the lexicon, within
+/// the limits in `options`.  Along with each complete anagram, this yields
+/// each dead end, with the letters that are left over, apart from near
+/// misses whose leftovers are words that the search order skipped, since
+/// those are complete anagrams written another way.  Required words come
+/// first in each anagram, and are made from the letters before anything
+/// else; if they can't be, there are no anagrams.
 pub fn anagrams<'a>(
-    term: &str,
+    letters: SortedLetters,
+    options: &AnagramOptions,
     lexi: &'a Lexicon<'a>,
-) -> impl Iterator<Item = (Vec<&'a Entry<'a>>, SortedLetters)> {
-    let letters = SortedLetters::from_word(term);
-    let filter = FilterBuilder::new()
-        .contained(letters)
-        .single_word(true.into())
-        .build();
-    let mut words = lexi
-        .entries()
-        .filter(|entry| filter.matches(entry))
-        .collect_vec();
-    words.sort_by_key(|entry| {
-        (
-            Reverse(lexi.rate(entry) as usize),
-            Reverse(entry.word().len()),
-            entry.word(),
-        )
-    });
-
-    let frame = AnagramFrame {
-        letters,
-        pos: 0,
-        partial_result: Vec::new(),
-        is_virgin: true,
-    };
+) -> Result<Anagrams<'a>, AnagramError> {
+    let (search, root) = Search::new(letters, options, lexi)?;
+    Ok(Anagrams {
+        search,
+        stack: root.into_iter().collect(),
+    })
+}
+
+/// The words of an anagram, plus any letters left over from a near miss.
+pub type Anagram<'a> = (Vec<AnagramWord<'a>>, SortedLetters);
+
+impl<'a> Search<'a> {
+    /// Gets ready to search, returning the first frame unless the required
+    /// words rule out every anagram.
+    fn new(
+        letters: SortedLetters,
+        options: &AnagramOptions,
+        lexi: &'a Lexicon<'a>,
+    ) -> Result<(Self, Option<AnagramFrame<'a>>), AnagramError> {
+        let max_words = options.max_words.unwrap_or(usize::MAX);
+        let mut remaining = Some(letters);
+        let mut required = Vec::with_capacity(options.required.len());
+        for word in &options.required {
+            let entry = lexi
+                .find(word)
+                .ok_or_else(|| AnagramError::NotAWord(word.clone()))?;
+            remaining = remaining.and_then(|letters| {
+                let (letters, blanks) = letters.minus_with_blanks(entry.letters())?;
+                required.push(AnagramWord { entry, blanks });
+                Some(letters)
+            });
+        }
+        let banned = options
+            .banned
+            .iter()
+            .map(|word| lexi.search_text(&word.to_lowercase()).into_owned())
+            .collect_vec();
+
+        let filter = FilterBuilder::new()
+            .contained(remaining)
+            .single_word((!options.phrases).then_some(true))
+            .length(
+                (options.min_word_len > 1)
+                    .then(|| LengthRange::new(options.min_word_len, usize::MAX)),
+            )
+            .build();
+        let mut words = match remaining {
+            Some(_) => lexi
+                .entries()
+                .filter(|entry| filter.matches(entry))
+                .filter(|entry| !banned.iter().any(|word| word == entry.text()))
+                .collect_vec(),
+            None => Vec::new(),
+        };
+        words.sort_by_key(|entry| {
+            (
+                Reverse(lexi.rate(entry) as usize),
+                Reverse(entry.len()),
+                entry.word(),
+            )
+        });
+
+        let root = match remaining {
+            Some(letters) if required.len() <= max_words => Some(AnagramFrame {
+                letters,
+                pos: 0,
+                end: words.len(),
+                partial_result: required,
+                is_virgin: true,
+            }),
+            _ => None,
+        };
 
-    Anagrams {
-        entries: words,
-        stack: vec![frame],
+        let short = words
+            .iter()
+            .filter(|entry| entry.letters().len() <= options.max_leftover)
+            .copied()
+            .collect_vec();
+        let search = Search {
+            lexi,
+            entries: words,
+            short,
+            max_words,
+            min_word_len: options.min_word_len,
+            max_leftover: options.max_leftover,
+            budget: Arc::new(Budget::with_limits(options.max_nodes, options.time_limit)),
+        };
+        Ok((search, root))
+    }
+
+    /// A copy of the search with a budget of its own, which has the same
+    /// deadline but only `max_nodes` words to try.
+    fn fork(&self, max_nodes: usize) -> Self {
+        Search {
+            budget: Arc::new(Budget::new(max_nodes, self.budget.deadline)),
+            ..self.clone()
+        }
+    }
+
+    /// Counts a word tried, and returns true if that's over the budget.
+    fn spend(&self) -> bool {
+        self.budget.spend()
+    }
+
+    /// True if the budget ran out, so there may be anagrams that weren't
+    /// found.
+    fn truncated(&self) -> bool {
+        self.budget.is_spent()
+    }
+
+    /// Whether the entry could be the next word of the frame's anagram.
+    /// The last word allowed must use up all of the letters, and no word
+    /// may leave fewer letters than the shortest word allowed, unless what
+    /// it leaves is few enough for a near miss.
+    fn fits(&self, frame: &AnagramFrame, entry: &Entry) -> bool {
+        let num_words = frame.partial_result.len() + 1;
+        let left = frame.letters.len().saturating_sub(entry.letters().len());
+        let near_miss = left <= self.max_leftover;
+        match num_words.cmp(&self.max_words) {
+            Ordering::Greater => false,
+            Ordering::Equal => near_miss,
+            Ordering::Less => near_miss || left >= self.min_word_len,
+        }
+    }
+
+    /// Whether some of the short entries, in any order, would use up the
+    /// frame's letters.  A dead end can leave words that come before `pos`,
+    /// such as TENT + N + "a", which the search also finds as TENT + A + N,
+    /// so it isn't really a near miss.
+    fn completes(&self, frame: &AnagramFrame<'a>) -> bool {
+        self.short.iter().any(|entry| {
+            self.extend(frame, entry, 0)
+                .is_some_and(|child| child.letters.is_empty() || self.completes(&child))
+        })
+    }
+
+    /// Makes the child frame for the next word of the frame's anagram, if
+    /// the word fits in the letters that are left.  The child frame starts
+    /// at the next entry, which eliminates duplicates like
+    ///  MEATS = ME + AT + "s"
+    ///  MEATS = AT + ME + "s"
+    fn extend(
+        &self,
+        frame: &AnagramFrame<'a>,
+        entry: &'a Entry<'a>,
+        pos: usize,
+    ) -> Option<AnagramFrame<'a>> {
+        if !self.fits(frame, entry) {
+            return None;
+        }
+        let (letters, blanks) = frame.letters.minus_with_blanks(entry.letters())?;
+        let mut partial_result = frame.partial_result.clone();
+        partial_result.push(AnagramWord { entry, blanks });
+        Some(AnagramFrame {
+            letters,
+            pos,
+            end: self.entries.len(),
+            partial_result,
+            is_virgin: true,
+        })
+    }
+}
+
+impl Anagrams<'_> {
+    /// True if the search stopped early because it ran out of nodes or
+    /// time, so there may be anagrams that it didn't find.
+    pub fn truncated(&self) -> bool {
+        self.search.truncated()
     }
 }
 
 impl<'a> Iterator for Anagrams<'a> {
-    type Item = (Vec<&'a Entry<'a>>, SortedLetters);
+    type Item = Anagram<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(frame) = self.stack.last_mut() {
+        while let Some(frame) = self.stack.last() {
             if frame.letters.is_empty() {
                 let frame = self.stack.pop().unwrap();
                 return Some((frame.partial_result, frame.letters));
             }
-            if let Some(entry) = self.entries.get(frame.pos) {
+            if frame.pos < frame.end {
+                let entry = self.search.entries[frame.pos];
+                if self.search.spend() {
+                    self.stack.clear();
+                    break;
+                }
+                let child = self.search.extend(frame, entry, frame.pos + 1);
+                let frame = self.stack.last_mut().unwrap();
                 frame.pos += 1;
-
-                if let Some(letters) = frame.letters.minus(entry.letters()) {
-                    let mut result = frame.partial_result.clone();
-                    result.push(entry);
-                    let new_frame = AnagramFrame {
-                        letters,
-                        // Start the child frame at the next entry, ensuring
-                        // that we eliminate duplicates like
-                        //  MEATS = ME + AT + "s"
-                        //  MEATS = AT + ME + "s"
-                        pos: frame.pos,
-                        partial_result: result,
-                        is_virgin: true,
-                    };
+                if let Some(child) = child {
                     frame.is_virgin = false;
-                    self.stack.push(new_frame);
+                    self.stack.push(child);
                 }
             } else {
                 let frame = self.stack.pop().unwrap();
-                if frame.is_virgin {
+                let skipped = frame.letters.len() <= self.search.max_leftover
+                    && self.search.completes(&frame);
+                if frame.is_virgin && !skipped {
                     return Some((frame.partial_result, frame.letters));
                 }
             }
@@ -90,3 +298,324 @@ impl<'a> Iterator for Anagrams<'a> {
         None
     }
 }
+
+pub fn run(opts: &AnagramOpts, lexi: &Lexicon) {
+    let letters = match lexi.parse_letters(&opts.letters) {
+        Ok(letters) => letters,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            process::exit(1);
+        }
+    };
+    let options = AnagramOptions {
+        phrases: opts.phrases,
+        max_words: opts.max_words,
+        min_word_len: opts.min_length,
+        required: opts.with.clone(),
+        banned: opts.without.clone(),
+        max_leftover: opts.leftover,
+        max_nodes: opts.max_nodes,
+        time_limit: opts.time_limit,
+    };
+    let limit = opts.limit.unwrap_or(usize::MAX);
+    let truncated = match opts.score {
+        Some(score) => best_anagrams(letters, &options, score, lexi).map(|mut results| {
+            print_anagrams(results.by_ref(), opts.leftover, limit, lexi);
+            results.truncated()
+        }),
+        None => {
+            let near_miss = |rest: &SortedLetters| rest.len() <= opts.leftover;
+            let threads = parallel::max_threads();
+            par_anagrams(letters, &options, lexi, threads, limit, near_miss).map(
+                |(results, truncated)| {
+                    print_anagrams(results.into_iter(), opts.leftover, limit, lexi);
+                    truncated
+                },
+            )
+        }
+    };
+    match truncated {
+        Ok(false) => {}
+        Ok(true) => eprintln!("{}", "Gave up before finding every anagram".yellow()),
+        Err(e) => {
+            eprintln!("{}", e.red());
+            process::exit(1);
+        }
+    }
+}
+
+/// Prints the anagrams, and the near misses that leave no more than
+/// `max_leftover` letters, with the leftover letters dimmed.
+fn print_anagrams<'a>(
+    results: impl Iterator<Item = (Vec<AnagramWord<'a>>, SortedLetters)>,
+    max_leftover: usize,
+    limit: usize,
+    lexi: &Lexicon,
+) {
+    results
+        .filter(|(_, rest)| rest.len() <= max_leftover)
+        .take(limit)
+        .for_each(|(words, rest)| {
+            let words = words.iter().map(|w| w.entry.phrase()).join(" ");
+            if rest.is_empty() {
+                println!("{words}");
+            } else {
+                println!("{words} {}", lexi.alphabet().spell(&rest).dimmed());
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{check, let_assert};
+
+    fn solutions(letters: &str, options: &AnagramOptions, lexi: &Lexicon) -> Vec<String> {
+        anagrams(SortedLetters::from_word(letters), options, lexi)
+            .unwrap()
+            .filter(|(_, rest)| rest.is_empty())
+            .map(|(words, _)| words.iter().map(|w| w.entry.phrase()).join(" + "))
+            .collect()
+    }
+
+    #[test]
+    fn test_phrases() {
+        let lexi = Lexicon::from_iter(["ice", "cream", "icecream (3,5)", "crime", "acre"]);
+        let phrases = AnagramOptions {
+            phrases: true,
+            ..Default::default()
+        };
+        check!(solutions("creamice", &AnagramOptions::default(), &lexi) == ["cream + ice"]);
+        check!(solutions("creamice", &phrases, &lexi) == ["ice cream", "cream + ice"]);
+    }
+
+    #[test]
+    fn test_options() {
+        let lexi = Lexicon::from_iter(["ten", "net", "tent", "an", "ant", "tan", "neat", "a", "n"]);
+        let all = |options: AnagramOptions| {
+            let mut found = solutions("tenant", &options, &lexi);
+            found.sort();
+            found
+        };
+        check!(
+            all(AnagramOptions::default())
+                == [
+                    "ant + net",
+                    "ant + ten",
+                    "net + tan",
+                    "tan + ten",
+                    "tent + a + n",
+                    "tent + an"
+                ]
+        );
+        let found = all(AnagramOptions {
+            max_words: Some(2),
+            ..Default::default()
+        });
+        check!(
+            found
+                == [
+                    "ant + net",
+                    "ant + ten",
+                    "net + tan",
+                    "tan + ten",
+                    "tent + an"
+                ]
+        );
+        let found = all(AnagramOptions {
+            min_word_len: 3,
+            ..Default::default()
+        });
+        check!(found == ["ant + net", "ant + ten", "net + tan", "tan + ten"]);
+        let found = all(AnagramOptions {
+            required: vec!["TEN".to_owned()],
+            banned: vec!["ant".to_owned()],
+            ..Default::default()
+        });
+        check!(found == ["ten + tan"]);
+        let found = all(AnagramOptions {
+            required: vec!["tent".to_owned()],
+            max_words: Some(2),
+            ..Default::default()
+        });
+        check!(found == ["tent + an"]);
+
+        let missing = AnagramOptions {
+            required: vec!["taunt".to_owned()],
+            ..Default::default()
+        };
+        let_assert!(Err(e) = anagrams(SortedLetters::from_word("tenant"), &missing, &lexi));
+        check!(e == AnagramError::NotAWord("taunt".to_owned()));
+        let unused = AnagramOptions {
+            required: vec!["neat".to_owned(), "tan".to_owned()],
+            ..Default::default()
+        };
+        check!(solutions("tenant", &unused, &lexi).is_empty());
+    }
+
+    fn near_misses(letters: &str, options: &AnagramOptions, lexi: &Lexicon) -> Vec<String> {
+        let mut found = anagrams(SortedLetters::from_word(letters), options, lexi)
+            .unwrap()
+            .filter(|(_, rest)| rest.len() <= options.max_leftover)
+            .map(|(words, rest)| {
+                let words = words.iter().map(|w| w.entry.phrase()).join(" + ");
+                format!("{words} + {}", rest.to_string().to_uppercase())
+            })
+            .collect_vec();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let lexi = Lexicon::from_iter(["ten", "net", "tent", "an", "ant", "tan", "a", "n"]);
+        let options = AnagramOptions {
+            max_leftover: 1,
+            ..Default::default()
+        };
+        check!(
+            near_misses("tenants", &options, &lexi)
+                == [
+                    "ant + net + S",
+                    "ant + ten + S",
+                    "net + tan + S",
+                    "tan + ten + S",
+                    "tent + a + n + S",
+                    "tent + an + S"
+                ]
+        );
+        let options = AnagramOptions {
+            max_leftover: 1,
+            max_words: Some(2),
+            min_word_len: 3,
+            ..Default::default()
+        };
+        check!(
+            near_misses("tenants", &options, &lexi)
+                == [
+                    "ant + net + S",
+                    "ant + ten + S",
+                    "net + tan + S",
+                    "tan + ten + S"
+                ]
+        );
+        let options = AnagramOptions {
+            max_leftover: 2,
+            ..Default::default()
+        };
+        check!(near_misses("tenantsy", &options, &lexi).contains(&"ant + net + SY".to_owned()));
+
+        // TENT + N leaves A, and TENT + AN leaves N, but both are words, so
+        // those are really the anagram TENT + A + N.
+        let options = AnagramOptions {
+            max_leftover: 2,
+            ..Default::default()
+        };
+        let found = near_misses("tenant", &options, &lexi);
+        check!(found.contains(&"tent + a + n + ".to_owned()));
+        check!(!found.iter().any(|a| a.starts_with("tent + n + ")));
+        check!(found.iter().filter(|a| a.starts_with("tent + ")).count() == 2);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let lexi = Lexicon::from_iter(["ten", "net", "tent", "an", "ant", "tan", "a", "n", "s"]);
+        let show = |results: Vec<(Vec<AnagramWord>, SortedLetters)>| {
+            results
+                .iter()
+                .map(|(words, rest)| {
+                    let words = words.iter().map(|w| w.entry.word()).join(" + ");
+                    format!("{words} + {rest}")
+                })
+                .collect_vec()
+        };
+        let cases = [
+            ("tenants", AnagramOptions::default()),
+            (
+                "tenantsy",
+                AnagramOptions {
+                    max_leftover: 1,
+                    ..Default::default()
+                },
+            ),
+            (
+                "tenant",
+                AnagramOptions {
+                    required: vec!["tent".to_owned()],
+                    ..Default::default()
+                },
+            ),
+            ("xyz", AnagramOptions::default()),
+        ];
+        for (letters, options) in cases {
+            let letters = SortedLetters::from_word(letters);
+            let expected = anagrams(letters, &options, &lexi).unwrap().collect_vec();
+            check!(!expected.is_empty());
+            for threads in [1, 2, 4] {
+                let (found, truncated) =
+                    par_anagrams(letters, &options, &lexi, threads, usize::MAX, |_| true).unwrap();
+                check!(!truncated);
+                check!(show(found) == show(expected.clone()));
+            }
+        }
+
+        let letters = SortedLetters::from_word("tenants");
+        let options = AnagramOptions::default();
+        let (found, _) =
+            par_anagrams(letters, &options, &lexi, 4, 2, |rest| rest.is_empty()).unwrap();
+        let expected = anagrams(letters, &options, &lexi)
+            .unwrap()
+            .filter(|(_, rest)| rest.is_empty())
+            .take(2)
+            .collect_vec();
+        check!(show(found) == show(expected));
+
+        // A node budget cuts off the same anagrams however many threads
+        // share the work.
+        let mut total = anagrams(letters, &options, &lexi).unwrap();
+        total.by_ref().for_each(drop);
+        let total = total.search.budget.nodes();
+        for max_nodes in (0..=total + 1).step_by(3) {
+            let options = AnagramOptions {
+                max_nodes: Some(max_nodes),
+                ..Default::default()
+            };
+            let mut sequential = anagrams(letters, &options, &lexi).unwrap();
+            let expected = sequential.by_ref().collect_vec();
+            for threads in [2, 4, 8] {
+                let (found, truncated) =
+                    par_anagrams(letters, &options, &lexi, threads, usize::MAX, |_| true).unwrap();
+                check!(truncated == sequential.truncated());
+                check!(show(found) == show(expected.clone()));
+            }
+            let expected = expected.into_iter().take(3).collect_vec();
+            let (found, _) = par_anagrams(letters, &options, &lexi, 4, 3, |_| true).unwrap();
+            check!(show(found) == show(expected));
+        }
+    }
+
+    #[test]
+    fn test_budget() {
+        let lexi = Lexicon::from_iter(["ten", "net", "tent", "an", "ant", "tan", "neat", "a", "n"]);
+        let letters = SortedLetters::from_word("tenant");
+        let mut all = anagrams(letters, &AnagramOptions::default(), &lexi).unwrap();
+        check!(all.by_ref().count() > 1);
+        check!(!all.truncated());
+
+        let options = AnagramOptions {
+            max_nodes: Some(3),
+            ..Default::default()
+        };
+        let mut limited = anagrams(letters, &options, &lexi).unwrap();
+        check!(limited.by_ref().count() == 0);
+        check!(limited.truncated());
+
+        let options = AnagramOptions {
+            time_limit: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let mut limited = anagrams(letters, &options, &lexi).unwrap();
+        limited.by_ref().for_each(drop);
+        check!(limited.truncated());
+    }
+}
@@ -0,0 +1,287 @@
+use super::{AnagramError, AnagramFrame, AnagramOptions, AnagramWord, Search};
+use crate::lexi::{Lexicon, Popularity, SortedLetters};
+use serde::Deserialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// What makes one anagram better than another, for a best-first search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum AnagramScore {
+    /// Fewest words first.
+    FewestWords,
+    /// Anagrams whose least popular word is most popular first.
+    Popularity,
+    /// Anagrams with the longest word first.
+    LongestWord,
+}
+
+impl AnagramScore {
+    /// How far from the best possible score any anagram that starts with
+    /// the frame's words can be, or for a finished anagram, how far it is.
+    /// Adding a word never lowers this, so once a finished anagram is the
+    /// lowest in the queue, nothing left can beat it.  Leftover letters in
+    /// a near miss count as one more word.
+    fn cost(self, frame: &AnagramFrame, done: bool, lexi: &Lexicon) -> usize {
+        let words = &frame.partial_result;
+        match self {
+            AnagramScore::FewestWords => words.len() + usize::from(!frame.letters.is_empty()),
+            AnagramScore::Popularity => {
+                let least = words
+                    .iter()
+                    .map(|w| lexi.rate(w.entry))
+                    .min()
+                    .unwrap_or(Popularity::High);
+                Popularity::High as usize - least as usize
+            }
+            AnagramScore::LongestWord => {
+                let longest = words
+                    .iter()
+                    .map(|w| w.entry.letters().len())
+                    .chain((!done).then_some(frame.letters.len()))
+                    .max()
+                    .unwrap_or_default();
+                usize::MAX - longest
+            }
+        }
+    }
+}
+
+/// Anagrams in order of an `AnagramScore`, best first.
+pub struct BestAnagrams<'a> {
+    search: Search<'a>,
+    score: AnagramScore,
+    queue: BinaryHeap<Node<'a>>,
+    /// The number of frames queued so far, which breaks ties so that
+    /// anagrams with the same score come out in the order they were found.
+    queued: usize,
+}
+
+struct Node<'a> {
+    cost: usize,
+    /// True if the frame is a complete anagram or a near miss, rather than
+    /// the start of one.
+    done: bool,
+    seq: usize,
+    frame: AnagramFrame<'a>,
+}
+
+impl Node<'_> {
+    /// The order to take nodes off the queue: lowest cost first, finished
+    /// anagrams before partial ones with the same cost, then oldest first.
+    fn key(&self) -> (Reverse<usize>, bool, Reverse<usize>) {
+        (Reverse(self.cost), self.done, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for Node<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Node<'_> {}
+
+impl PartialOrd for Node<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Finds the same anagrams as `anagrams`, within the same limits, but
+/// yields them best first by `score`, so the best few can be had without
+/// finding them all.  Dead ends are only yielded if they are near misses,
+/// leaving no more than `options.max_leftover` letters.
+pub fn best_anagrams<'a>(
+    letters: SortedLetters,
+    options: &AnagramOptions,
+    score: AnagramScore,
+    lexi: &'a Lexicon<'a>,
+) -> Result<BestAnagrams<'a>, AnagramError> {
+    let (search, root) = Search::new(letters, options, lexi)?;
+    let mut best = BestAnagrams {
+        search,
+        score,
+        queue: BinaryHeap::new(),
+        queued: 0,
+    };
+    if let Some(root) = root {
+        best.push(root, false);
+    }
+    Ok(best)
+}
+
+impl<'a> BestAnagrams<'a> {
+    /// True if the search stopped early because it ran out of nodes or
+    /// time, so there may be anagrams that it didn't find.
+    pub fn truncated(&self) -> bool {
+        self.search.truncated()
+    }
+
+    /// True if there is nothing left to search, so every anagram has been
+    /// yielded.
+    pub fn is_exhausted(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn push(&mut self, frame: AnagramFrame<'a>, done: bool) {
+        let done = done || frame.letters.is_empty();
+        let cost = self.score.cost(&frame, done, self.search.lexi);
+        self.queue.push(Node {
+            cost,
+            done,
+            seq: self.queued,
+            frame,
+        });
+        self.queued += 1;
+    }
+}
+
+impl<'a> Iterator for BestAnagrams<'a> {
+    type Item = (Vec<AnagramWord<'a>>, SortedLetters);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Node { frame, done, .. }) = self.queue.pop() {
+            if done {
+                return Some((frame.partial_result, frame.letters));
+            }
+            let mut dead_end = true;
+            for pos in frame.pos..frame.end {
+                if self.search.spend() {
+                    self.queue.clear();
+                    return None;
+                }
+                let entry = self.search.entries[pos];
+                if let Some(child) = self.search.extend(&frame, entry, pos + 1) {
+                    self.push(child, false);
+                    dead_end = false;
+                }
+            }
+            // Its score may be better than it will be once it's finished,
+            // so it goes back in the queue rather than straight out.
+            if dead_end
+                && frame.letters.len() <= self.search.max_leftover
+                && !self.search.completes(&frame)
+            {
+                self.push(frame, true);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anagrams;
+    use assert2::check;
+    use itertools::Itertools;
+
+    fn ranked(score: AnagramScore, lexi: &Lexicon) -> Vec<String> {
+        best_anagrams(
+            SortedLetters::from_word("tenant"),
+            &AnagramOptions::default(),
+            score,
+            lexi,
+        )
+        .unwrap()
+        .map(|(words, _)| words.iter().map(|w| w.entry.word()).join(" + "))
+        .collect()
+    }
+
+    #[test]
+    fn test_best_first() {
+        let lexi =
+            Lexicon::from_iter(["ten", "net", "tent", "an", "ant", "tan", "a", "n", "tenant"]);
+
+        let fewest = ranked(AnagramScore::FewestWords, &lexi);
+        check!(fewest[0] == "tenant");
+        check!(fewest.last().unwrap() == "tent + a + n");
+        let counts = fewest.iter().map(|a| a.split(" + ").count()).collect_vec();
+        check!(counts.windows(2).all(|w| w[0] <= w[1]));
+
+        let longest = ranked(AnagramScore::LongestWord, &lexi);
+        check!(longest[..2] == ["tenant", "tent + an"]);
+
+        let mut all = anagrams(
+            SortedLetters::from_word("tenant"),
+            &AnagramOptions::default(),
+            &lexi,
+        )
+        .unwrap()
+        .filter(|(_, rest)| rest.is_empty())
+        .map(|(words, _)| words.iter().map(|w| w.entry.word()).join(" + "))
+        .collect_vec();
+        all.sort();
+        for score in [
+            AnagramScore::FewestWords,
+            AnagramScore::Popularity,
+            AnagramScore::LongestWord,
+        ] {
+            let mut found = ranked(score, &lexi);
+            found.sort();
+            check!(found == all);
+        }
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let lexi = Lexicon::from_iter(["ten", "net", "tent", "an", "ant", "tan", "a", "n"]);
+        let options = AnagramOptions {
+            max_leftover: 1,
+            ..Default::default()
+        };
+        let letters = SortedLetters::from_word("tenants");
+        let longest = best_anagrams(letters, &options, AnagramScore::LongestWord, &lexi)
+            .unwrap()
+            .collect_vec();
+        check!(longest.len() == 6);
+        check!(longest[0].0[0].entry.word() == "tent");
+        check!(longest.iter().all(|(_, rest)| rest.to_string() == "s"));
+
+        let exact = best_anagrams(
+            SortedLetters::from_word("tenant"),
+            &options,
+            AnagramScore::FewestWords,
+            &lexi,
+        )
+        .unwrap()
+        .collect_vec();
+        // The two-word anagrams beat the near misses, which count their
+        // leftovers as a third word.
+        check!(exact[..5].iter().all(|(_, rest)| rest.is_empty()));
+        check!(exact.iter().any(|(_, rest)| !rest.is_empty()));
+        // TENT + N would leave A, which is a word, so it's only found as
+        // TENT + A + N.
+        check!(!exact
+            .iter()
+            .any(|(words, rest)| words[0].entry.word() == "tent" && !rest.is_empty()));
+    }
+
+    #[test]
+    fn test_popularity() {
+        let lexi =
+            Lexicon::from_iter(["ten", "net", "ant", "tan"]).with_popular_words(["net", "tan"], 2);
+        let best = ranked(AnagramScore::Popularity, &lexi);
+        check!(best[0] == "net + tan");
+        check!(best.len() == 4);
+    }
+
+    #[test]
+    fn test_exhausted() {
+        let lexi = Lexicon::from_iter(["ten", "net", "ant", "tan", "tenant"]);
+        let letters = SortedLetters::from_word("tenant");
+        let options = AnagramOptions::default();
+        let mut best = best_anagrams(letters, &options, AnagramScore::FewestWords, &lexi).unwrap();
+        check!(best.next().unwrap().0[0].entry.word() == "tenant");
+        check!(!best.is_exhausted());
+        check!(best.by_ref().count() == 4);
+        check!(best.is_exhausted());
+    }
+}
@@ -0,0 +1,188 @@
+use super::{Anagram, AnagramError, AnagramFrame, AnagramOptions, Anagrams, Search};
+use crate::budget::Budget;
+use crate::lexi::{Lexicon, SortedLetters};
+use crate::parallel;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// What the search found in one share of the top-level words.
+struct Chunk<'a> {
+    /// The anagrams kept, each with the number of words tried before it
+    /// turned up.
+    found: Vec<(usize, Anagram<'a>)>,
+    /// The number of words tried in all.
+    nodes: usize,
+    /// True if the chunk ran out of budget, or was called off, before it
+    /// tried all of its words.
+    gave_up: bool,
+}
+
+/// A finished chunk, as far as `Progress` needs to know.
+struct Finished {
+    end: usize,
+    found: usize,
+    nodes: usize,
+    gave_up: bool,
+}
+
+/// Which chunks have finished, so that the ones after the last result that
+/// will be needed can be called off.
+struct Progress {
+    /// The start of the first chunk that hasn't finished.
+    next: usize,
+    /// The anagrams found, and the words tried, before `next`.
+    found: usize,
+    nodes: usize,
+    /// Chunks that have finished after `next`, by start.
+    finished: BTreeMap<usize, Finished>,
+    /// Chunks that start here or later aren't needed.
+    cutoff: usize,
+    /// The budgets of the chunks still running, by start.
+    running: Vec<(usize, Arc<Budget>)>,
+}
+
+impl Progress {
+    /// Notes that a chunk has finished, and calls off the chunks after it
+    /// if everything that will be kept has now been found.
+    fn finish(&mut self, range: Range<usize>, chunk: &Chunk, max: usize, max_nodes: usize) {
+        self.running.retain(|&(start, _)| start != range.start);
+        self.finished.insert(
+            range.start,
+            Finished {
+                end: range.end,
+                found: chunk.found.len(),
+                nodes: chunk.nodes,
+                gave_up: chunk.gave_up,
+            },
+        );
+        while self.next < self.cutoff {
+            let Some(done) = self.finished.remove(&self.next) else {
+                break;
+            };
+            self.next = done.end;
+            self.found += done.found;
+            self.nodes += done.nodes;
+            if done.gave_up || self.found >= max || self.nodes > max_nodes {
+                self.cutoff = self.next;
+            }
+        }
+        for (start, budget) in &self.running {
+            if *start >= self.cutoff {
+                budget.give_up();
+            }
+        }
+    }
+}
+
+/// Finds what `anagrams` would yield, keeping the results that `keep`
+/// accepts, up to `max` of them.  The words at the top level of the search
+/// are split up between `threads` threads.  Each share counts the words it
+/// tries, and the counts are added up in order afterwards, so that a node
+/// budget cuts off the same results as it would for `anagrams`.  Also
+/// returns true if the budget ran out.
+pub fn par_anagrams<'a>(
+    letters: SortedLetters,
+    options: &AnagramOptions,
+    lexi: &'a Lexicon<'a>,
+    threads: usize,
+    max: usize,
+    keep: impl Fn(&SortedLetters) -> bool + Sync,
+) -> Result<(Vec<Anagram<'a>>, bool), AnagramError> {
+    let (search, root) = Search::new(letters, options, lexi)?;
+    let Some(root) = root else {
+        return Ok((Vec::new(), false));
+    };
+    // The root is a dead end if no word fits, which only the search as a
+    // whole can tell.
+    let fits = |pos: usize| search.extend(&root, search.entries[pos], pos + 1).is_some();
+    if max == 0 || root.letters.is_empty() || !(0..root.end).any(fits) {
+        let mut anagrams = Anagrams {
+            search,
+            stack: vec![root],
+        };
+        let results = anagrams
+            .by_ref()
+            .filter(|(_, rest)| keep(rest))
+            .take(max)
+            .collect_vec();
+        return Ok((results, anagrams.truncated()));
+    }
+
+    let max_nodes = search.budget.max_nodes;
+    let progress = Mutex::new(Progress {
+        next: 0,
+        found: 0,
+        nodes: 0,
+        finished: BTreeMap::new(),
+        cutoff: usize::MAX,
+        running: Vec::new(),
+    });
+    let chunks = parallel::map_ranges(root.end, threads, 1, |range| {
+        let search = {
+            let mut progress = progress.lock().unwrap();
+            if range.start >= progress.cutoff {
+                return Chunk {
+                    found: Vec::new(),
+                    nodes: 0,
+                    gave_up: true,
+                };
+            }
+            // Whatever the chunks before this one try comes out of the
+            // same budget.
+            let search = search.fork(max_nodes.saturating_sub(progress.nodes));
+            progress
+                .running
+                .push((range.start, Arc::clone(&search.budget)));
+            search
+        };
+        let mut anagrams = Anagrams {
+            search,
+            stack: vec![AnagramFrame {
+                pos: range.start,
+                end: range.end,
+                is_virgin: false,
+                ..root.clone()
+            }],
+        };
+        let mut found = Vec::new();
+        while found.len() < max {
+            let Some(anagram) = anagrams.next() else {
+                break;
+            };
+            if keep(&anagram.1) {
+                found.push((anagrams.search.budget.nodes(), anagram));
+            }
+        }
+        let chunk = Chunk {
+            found,
+            nodes: anagrams.search.budget.nodes(),
+            gave_up: anagrams.truncated(),
+        };
+        progress
+            .lock()
+            .unwrap()
+            .finish(range, &chunk, max, max_nodes);
+        chunk
+    });
+
+    let mut results = Vec::new();
+    let mut offset = 0;
+    for chunk in chunks {
+        for (nodes, anagram) in chunk.found {
+            if offset + nodes > max_nodes {
+                return Ok((results, true));
+            }
+            results.push(anagram);
+            if results.len() == max {
+                return Ok((results, false));
+            }
+        }
+        if chunk.gave_up {
+            return Ok((results, true));
+        }
+        offset += chunk.nodes;
+    }
+    Ok((results, false))
+}
@@ -0,0 +1,61 @@
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::time::{Duration, Instant};
+
+/// How often to check the clock, in nodes.
+const CLOCK_INTERVAL: usize = 1024;
+
+/// How much work a search may do, in nodes: the words an anagram search
+/// tries, or the positions a Ghost solver works out.  It can be told to
+/// give up from another thread.
+pub struct Budget {
+    /// The number of nodes so far.
+    nodes: AtomicUsize,
+    pub max_nodes: usize,
+    pub deadline: Option<Instant>,
+    spent: AtomicBool,
+}
+
+impl Budget {
+    pub fn new(max_nodes: usize, deadline: Option<Instant>) -> Self {
+        Budget {
+            nodes: AtomicUsize::new(0),
+            max_nodes,
+            deadline,
+            spent: AtomicBool::new(false),
+        }
+    }
+
+    /// A budget that starts now, with no limit for the limits not given.
+    pub fn with_limits(max_nodes: Option<usize>, time_limit: Option<Duration>) -> Self {
+        Budget::new(
+            max_nodes.unwrap_or(usize::MAX),
+            time_limit.map(|limit| Instant::now() + limit),
+        )
+    }
+
+    /// Counts a node, and returns true if that's over the budget.
+    pub fn spend(&self) -> bool {
+        let nodes = self.nodes.fetch_add(1, atomic::Ordering::Relaxed);
+        let out_of_time = nodes.is_multiple_of(CLOCK_INTERVAL)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        if nodes >= self.max_nodes || out_of_time {
+            self.give_up();
+        }
+        self.is_spent()
+    }
+
+    /// The number of nodes so far.
+    pub fn nodes(&self) -> usize {
+        self.nodes.load(atomic::Ordering::Relaxed)
+    }
+
+    pub fn give_up(&self) {
+        self.spent.store(true, atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_spent(&self) -> bool {
+        self.spent.load(atomic::Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,245 @@
+use crate::lexi::Entry;
+use crate::lexi::Lexicon;
+use crate::ConundrumOpts;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::process;
+use thiserror::Error;
+
+/// Conundrums are always nine letters long.
+pub const LEN: usize = 9;
+
+/// How many times to reshuffle a word before giving up on it.
+const MAX_SHUFFLES: usize = 100;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConundrumError {
+    #[error("Expected {LEN} letters, got {0}")]
+    WrongLength(usize),
+    #[error("Invalid character: {0:?}")]
+    InvalidChar(char),
+}
+
+/// A scrambled word, and the only word that unscrambles it.
+#[derive(Debug, Clone)]
+pub struct Conundrum<'a> {
+    pub scramble: String,
+    pub answer: &'a Entry<'a>,
+}
+
+/// Finds all the nine-letter words with no other anagram in the lexicon,
+/// most popular first.
+pub fn candidates<'a>(lexi: &'a Lexicon<'a>) -> Vec<&'a Entry<'a>> {
+    let mut words = lexi
+        .entries()
+        .filter(|entry| entry.is_single_word() && entry.is_in_alphabet() && entry.len() == LEN)
+        .filter(|entry| lexi.solve_anagram(entry.letters()).count() == 1)
+        .collect_vec();
+    words.sort_by_key(|entry| entry.rank().unwrap_or(usize::MAX));
+    words
+}
+
+/// A scramble is too easy if it is a word itself, or if it splits cleanly
+/// into two words.  Only splits that read straight off the scramble count:
+/// its letters always make the answer, and often make other phrases, so
+/// checking them with `solve_anagram` or `anagrams` would reject everything.
+pub fn is_too_easy(scramble: &str, lexi: &Lexicon) -> bool {
+    lexi.contains(scramble)
+        || scramble
+            .char_indices()
+            .skip(1)
+            .any(|(k, _)| lexi.contains(&scramble[..k]) && lexi.contains(&scramble[k..]))
+}
+
+/// Shuffles the word until it is no longer too easy to spot.  Returns None
+/// if no acceptable scramble turns up.
+pub fn scramble(word: &str, lexi: &Lexicon, rng: &mut StdRng) -> Option<String> {
+    let mut letters = word.chars().collect_vec();
+    (0..MAX_SHUFFLES).find_map(|_| {
+        letters.shuffle(rng);
+        let scramble = letters.iter().collect::<String>();
+        (!is_too_easy(&scramble, lexi)).then_some(scramble)
+    })
+}
+
+/// Generates conundrums from a random selection of the popular candidates,
+/// or of all the candidates if there aren't enough popular ones, most
+/// popular first.
+pub fn generate<'a>(lexi: &'a Lexicon<'a>, count: usize, rng: &mut StdRng) -> Vec<Conundrum<'a>> {
+    let candidates = candidates(lexi);
+    let popular = candidates
+        .iter()
+        .take_while(|entry| entry.rank().is_some())
+        .count();
+    // If there aren't enough popular words, choose from all of them.
+    let pool = if popular < count {
+        &candidates[..]
+    } else {
+        &candidates[..popular]
+    };
+
+    let mut chosen = pool.choose_multiple(rng, count).copied().collect_vec();
+    chosen.sort_by_key(|entry| (entry.rank().unwrap_or(usize::MAX), entry.word()));
+    chosen
+        .into_iter()
+        .filter_map(|answer| {
+            let scramble = scramble(answer.text(), lexi, rng)?;
+            Some(Conundrum { scramble, answer })
+        })
+        .collect()
+}
+
+/// Finds the words that unscramble a conundrum.
+pub fn solve<'a>(
+    scramble: &str,
+    lexi: &'a Lexicon<'a>,
+) -> Result<Vec<&'a Entry<'a>>, ConundrumError> {
+    let scramble = lexi.normalize(scramble.trim()).to_lowercase();
+    if let Some(ch) = scramble.chars().find(|ch| !ch.is_alphabetic()) {
+        return Err(ConundrumError::InvalidChar(ch));
+    }
+    let letters = lexi
+        .alphabet()
+        .letters(&scramble)
+        .map_err(ConundrumError::InvalidChar)?;
+    let len = scramble.chars().count();
+    if len != LEN {
+        return Err(ConundrumError::WrongLength(len));
+    }
+    Ok(lexi
+        .solve_anagram(&letters)
+        .filter(|entry| entry.is_single_word())
+        .collect())
+}
+
+/// Makes a random number generator, seeded if requested so that the same
+/// conundrums come out every time.
+pub fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Runs the `wf conundrum` subcommand.
+pub fn run(opts: &ConundrumOpts, lexi: &Lexicon) {
+    if let Some(scramble) = &opts.scramble {
+        match solve(scramble, lexi) {
+            Ok(answers) if answers.is_empty() => println!("{}", "No solution".red()),
+            Ok(answers) => {
+                for answer in answers {
+                    println!("{}", answer.word().green());
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e.red());
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut rng = rng(opts.seed);
+    for conundrum in generate(lexi, opts.count, &mut rng) {
+        println!(
+            "{}  {}",
+            conundrum.scramble.to_uppercase(),
+            conundrum.answer.word().dimmed()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexi::SortedLetters;
+    use assert2::check;
+    use std::collections::BTreeSet;
+
+    fn lexicon() -> Lexicon<'static> {
+        Lexicon::from_iter([
+            "countdown",
+            "down",
+            "count",
+            "town",
+            "cod",
+            "unwont",
+            "dance",
+            "canoeists",
+            "coastline",
+            "sectional",
+            "lactones",
+            "alone",
+        ])
+    }
+
+    #[test]
+    fn test_candidates() {
+        let lex = lexicon();
+        let words = candidates(&lex).iter().map(|e| e.word()).collect_vec();
+        // "sectional" has another anagram, so it can't be used.
+        check!(words == ["countdown", "canoeists"]);
+    }
+
+    #[test]
+    fn test_is_too_easy() {
+        let lex = lexicon();
+        check!(is_too_easy("countdown", &lex));
+        check!(is_too_easy("downcount", &lex));
+        check!(!is_too_easy("towncodnu", &lex));
+        // The letters make "down count", but not in that order.
+        check!(!is_too_easy("nwodtnuoc", &lex));
+    }
+
+    #[test]
+    fn test_scramble() {
+        let lex = lexicon();
+        let mut rng = rng(Some(1));
+        let scramble = scramble("countdown", &lex, &mut rng).unwrap();
+        check!(SortedLetters::from_word(&scramble) == SortedLetters::from_word("countdown"));
+        check!(!is_too_easy(&scramble, &lex));
+    }
+
+    #[test]
+    fn test_generate() {
+        let lex = lexicon();
+        let mut rng = rng(Some(1));
+        let conundrums = generate(&lex, 5, &mut rng);
+        let answers = conundrums.iter().map(|c| c.answer.word()).collect_vec();
+        check!(answers == ["canoeists", "countdown"]);
+    }
+
+    #[test]
+    fn test_generate_few_popular() {
+        let lex = Lexicon::from_iter(["alphabets", "canoeists", "countdown", "something"])
+            .with_popular_words(["something"], 1);
+        let answers = (0..20)
+            .flat_map(|seed| generate(&lex, 2, &mut rng(Some(seed))))
+            .map(|c| c.answer.word())
+            .collect::<BTreeSet<_>>();
+        // Not just the popular word and the first of the rest.
+        check!(answers.len() == 4);
+    }
+
+    #[test]
+    fn test_solve() {
+        let lex = lexicon();
+        let answers = solve("NWODTNUOC", &lex).unwrap();
+        check!(answers.iter().map(|e| e.word()).collect_vec() == ["countdown"]);
+        check!(solve("nwodtnuo", &lex).err() == Some(ConundrumError::WrongLength(8)));
+    }
+
+    #[test]
+    fn test_spanish() {
+        let spanish = "spanish".parse().unwrap();
+        let lex = Lexicon::new(["compañero", "compa", "ñero"], spanish, Default::default());
+        check!(is_too_easy("ñerocompa", &lex));
+        check!(!is_too_easy("ñecomproa", &lex));
+        let conundrums = generate(&lex, 1, &mut rng(Some(1)));
+        check!(conundrums.iter().map(|c| c.answer.word()).collect_vec() == ["compañero"]);
+        check!(solve(&conundrums[0].scramble, &lex).unwrap().len() == 1);
+    }
+}
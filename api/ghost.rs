@@ -0,0 +1,244 @@
+mod solver;
+
+pub use solver::Solver;
+
+use crate::lexi::Entry;
+use crate::lexi::Lexicon;
+use crate::lexi::Popularity;
+use crate::GhostOpts;
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::process;
+
+/// Words shorter than this don't end the game.
+pub const DEFAULT_MIN_LEN: usize = 4;
+
+/// Which moves are legal when adding a letter to a Ghost fragment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Rules {
+    /// Letters may only be appended, and the fragment must remain the
+    /// start of a word.
+    Ghost,
+    /// Letters may be added at either end, and the fragment must remain
+    /// somewhere inside a word.
+    Superghost,
+}
+
+impl Display for Rules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rules::Ghost => write!(f, "Ghost"),
+            Rules::Superghost => write!(f, "Superghost"),
+        }
+    }
+}
+
+/// Which end of the fragment a letter was added to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Side {
+    Front,
+    Back,
+}
+
+/// A legal move from a fragment: the tile added, and the best rating and
+/// frequency of any word that the new fragment can still become.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    pub side: Side,
+    pub letter: String,
+    pub fragment: String,
+    pub rating: Popularity,
+    pub score: Option<u64>,
+}
+
+/// Finds words that are the fragment plus one tile at either end.
+pub fn plus_one<'a>(fragment: &str, lexi: &'a Lexicon<'a>) -> impl Iterator<Item = &'a Entry<'a>> {
+    let fragment = split(fragment, lexi);
+    lexi.entries().filter(move |entry| {
+        let Some(fragment) = &fragment else {
+            return false;
+        };
+        entry.is_single_word()
+            && entry.is_in_alphabet()
+            && split(entry.text(), lexi).is_some_and(|word| {
+                word.len() == fragment.len() + 1
+                    && (word.starts_with(fragment) || word.ends_with(fragment))
+            })
+    })
+}
+
+/// Finds all the tiles that can be added to the fragment so that it is
+/// still part of a word under the given rules.  Results are ordered by side
+/// and then by the tile's place in the alphabet.
+pub fn extensions(fragment: &str, rules: Rules, lexi: &Lexicon) -> Vec<Extension> {
+    let Some(fragment) = split(fragment, lexi) else {
+        return Vec::new();
+    };
+    let mut best: BTreeMap<(Side, u8), (Popularity, Option<u64>)> = BTreeMap::new();
+
+    // Fragments are built from the alphabet's tiles, so other words can't
+    // come up.
+    for entry in lexi
+        .entries()
+        .filter(|entry| entry.is_single_word() && entry.is_in_alphabet())
+    {
+        let Some(word) = split(entry.text(), lexi) else {
+            continue;
+        };
+        let rating = (lexi.rate(entry), entry.frequency());
+        for pos in occurrences(&word, &fragment) {
+            let end = pos + fragment.len();
+            let mut moves = Vec::with_capacity(2);
+            match rules {
+                Rules::Ghost => {
+                    if pos == 0 && end < word.len() {
+                        moves.push((Side::Back, word[end]));
+                    }
+                }
+                Rules::Superghost => {
+                    if pos > 0 {
+                        moves.push((Side::Front, word[pos - 1]));
+                    }
+                    if end < word.len() {
+                        moves.push((Side::Back, word[end]));
+                    }
+                }
+            }
+            for key in moves {
+                let slot = best.entry(key).or_insert(rating);
+                *slot = (*slot).max(rating);
+            }
+        }
+    }
+
+    let fragment = spell(&fragment, lexi);
+    best.into_iter()
+        .map(|((side, tile), (rating, score))| {
+            let letter = lexi.alphabet().tile(tile as usize).to_owned();
+            let fragment = match side {
+                Side::Front => format!("{letter}{fragment}"),
+                Side::Back => format!("{fragment}{letter}"),
+            };
+            Extension {
+                side,
+                letter,
+                fragment,
+                rating,
+                score,
+            }
+        })
+        .collect()
+}
+
+/// Runs the `wf ghost` subcommand.
+pub fn run(opts: &GhostOpts, lexi: &Lexicon) {
+    let rules = match opts.rules {
+        Some(rules) => vec![rules],
+        None => vec![Rules::Ghost, Rules::Superghost],
+    };
+
+    for rules in rules {
+        let outcome = Solver::new(lexi, rules, opts.players, opts.min_length)
+            .map(|solver| solver.with_limits(opts.max_nodes, opts.time_limit))
+            .and_then(|mut solver| solver.solve(&opts.fragment));
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                eprintln!("{}", format!("{rules}: {e}").red());
+                process::exit(1);
+            }
+        };
+        if outcome.is_winning() {
+            println!("{rules}: {}", "player to move wins".green());
+            for m in outcome.winning_moves() {
+                println!("  {} (player {} loses)", m.fragment, m.loser + 1);
+            }
+        } else {
+            println!("{rules}: {}", "player to move loses".red());
+        }
+        if outcome.partial {
+            eprintln!("{}", "  Gave up before working out every move".yellow());
+        }
+    }
+}
+
+/// Splits a word or fragment into the lexicon's tiles, or returns None if
+/// it has a letter that isn't on any tile.
+fn split(text: &str, lexi: &Lexicon) -> Option<Vec<u8>> {
+    let tiles = lexi.alphabet().split(&lexi.normalize(text)).ok()?;
+    Some(tiles.into_iter().map(|idx| idx as u8).collect())
+}
+
+/// Spells out some tiles as text.
+fn spell(tiles: &[u8], lexi: &Lexicon) -> String {
+    tiles
+        .iter()
+        .map(|&idx| lexi.alphabet().tile(idx as usize))
+        .collect()
+}
+
+/// Returns the starting position of every occurrence of `needle` in
+/// `haystack`, including overlapping ones.
+fn occurrences<'a>(haystack: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    (0..=haystack.len().saturating_sub(needle.len()))
+        .filter(move |&pos| haystack[pos..].starts_with(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+    use itertools::Itertools;
+
+    fn letters(extensions: &[Extension], side: Side) -> String {
+        extensions
+            .iter()
+            .filter(|ext| ext.side == side)
+            .map(|ext| ext.letter.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_plus_one() {
+        let lex = Lexicon::from_iter(["ape", "cape", "apex", "tapes", "apes"]);
+        let words = plus_one("APE", &lex).map(|e| e.word()).collect_vec();
+        check!(words == ["cape", "apex", "apes"]);
+    }
+
+    #[test]
+    fn test_ghost_extensions() {
+        let lex = Lexicon::from_iter(["banana", "bandit", "cabana", "abandon"]);
+        let exts = extensions("ban", Rules::Ghost, &lex);
+        check!(letters(&exts, Side::Front) == "");
+        check!(letters(&exts, Side::Back) == "ad");
+        check!(exts[0].fragment == "bana");
+    }
+
+    #[test]
+    fn test_superghost_extensions() {
+        let lex = Lexicon::from_iter(["banana", "bandit", "cabana", "abandon"]);
+        let exts = extensions("ana", Rules::Superghost, &lex);
+        check!(letters(&exts, Side::Front) == "bn");
+        check!(letters(&exts, Side::Back) == "n");
+    }
+
+    #[test]
+    fn test_spanish_extensions() {
+        let spanish = "spanish".parse().unwrap();
+        let words = ["niño", "niños", "nieto", "nietos", "chorro", "calle"];
+        let lex = Lexicon::new(words, spanish, Default::default());
+        let exts = extensions("ni", Rules::Ghost, &lex);
+        check!(exts.iter().map(|ext| ext.fragment.as_str()).collect_vec() == ["nie", "niñ"]);
+        let exts = extensions("o", Rules::Superghost, &lex);
+        check!(letters(&exts, Side::Front) == "chñrrt");
+        let exts = extensions("ca", Rules::Ghost, &lex);
+        check!(letters(&exts, Side::Back) == "ll");
+        // "chorro" starts with CH, not C.
+        check!(letters(&extensions("c", Rules::Ghost, &lex), Side::Back) == "a");
+        let words = plus_one("niñ", &lex).map(|e| e.word()).collect_vec();
+        check!(words == ["niño"]);
+    }
+}
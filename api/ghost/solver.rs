@@ -0,0 +1,340 @@
+use super::Rules;
+use super::Side;
+use super::{spell, split};
+use crate::budget::Budget;
+use crate::lexi::{Lexicon, TileWords};
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SolveError {
+    #[error("Need at least 2 players")]
+    TooFewPlayers,
+    #[error("{0:?} is not part of any word")]
+    NotAFragment(String),
+    #[error("{0:?} is already a word")]
+    AlreadyAWord(String),
+}
+
+/// A possible move from a position, and who loses if it is played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub side: Side,
+    pub letter: String,
+    pub fragment: String,
+    /// The losing player, counted from the player making this move: 0 is
+    /// the mover, 1 is the next player, and so on.
+    pub loser: usize,
+}
+
+impl Move {
+    pub fn is_winning(&self) -> bool {
+        self.loser != 0
+    }
+}
+
+/// The game-theoretic value of a Ghost position.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// The losing player under best play, counted from the player to move.
+    pub loser: usize,
+    /// Every legal move, ordered by side and then tile.
+    pub moves: Vec<Move>,
+    /// True if the solver gave up before working out every move, so the
+    /// losers may be wrong.
+    pub partial: bool,
+}
+
+impl Outcome {
+    /// True if the player to move can avoid losing.
+    pub fn is_winning(&self) -> bool {
+        self.loser != 0
+    }
+
+    pub fn winning_moves(&self) -> impl Iterator<Item = &Move> {
+        self.moves.iter().filter(|m| m.is_winning())
+    }
+}
+
+/// Solves Ghost positions by exhaustive search with memoization, within a
+/// budget of positions worked out.
+///
+/// A player loses by completing a word of at least `min_len` letters, or by
+/// making a fragment that is not part of any such word.  With more than two
+/// players, each player avoids losing if they can, and otherwise prefers the
+/// move that makes the next player in turn lose.
+pub struct Solver<'a> {
+    lexi: &'a Lexicon<'a>,
+    /// The lexicon's words split into tiles, since moves are the alphabet's
+    /// tiles and words with any other letters can't come up.
+    words: &'a TileWords,
+    rules: Rules,
+    players: usize,
+    min_len: usize,
+    memo: HashMap<Vec<u8>, usize>,
+    budget: Budget,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(
+        lexi: &'a Lexicon<'a>,
+        rules: Rules,
+        players: usize,
+        min_len: usize,
+    ) -> Result<Self, SolveError> {
+        if players < 2 {
+            return Err(SolveError::TooFewPlayers);
+        }
+        Ok(Self {
+            lexi,
+            words: lexi.tile_words(),
+            rules,
+            players,
+            min_len,
+            memo: HashMap::new(),
+            budget: Budget::with_limits(None, None),
+        })
+    }
+
+    /// Gives up after working out `max_nodes` positions, or after
+    /// `time_limit`.
+    pub fn with_limits(mut self, max_nodes: Option<usize>, time_limit: Option<Duration>) -> Self {
+        self.budget = Budget::with_limits(max_nodes, time_limit);
+        self
+    }
+
+    pub fn solve(&mut self, fragment: &str) -> Result<Outcome, SolveError> {
+        let Some(fragment) = split(fragment, self.lexi) else {
+            return Err(SolveError::NotAFragment(fragment.to_lowercase()));
+        };
+        if self.occurrences(&fragment).is_empty() {
+            return Err(SolveError::NotAFragment(spell(&fragment, self.lexi)));
+        }
+        if self.is_word(&fragment) {
+            return Err(SolveError::AlreadyAWord(spell(&fragment, self.lexi)));
+        }
+
+        let moves = self
+            .moves(&fragment)
+            .into_iter()
+            .map(|(side, tile, next)| {
+                let loser = self.play(&next);
+                Move {
+                    side,
+                    letter: self.lexi.alphabet().tile(tile as usize).to_owned(),
+                    fragment: spell(&next, self.lexi),
+                    loser,
+                }
+            })
+            .collect::<Vec<_>>();
+        let loser = self.choose(moves.iter().map(|m| m.loser));
+        Ok(Outcome {
+            loser,
+            moves,
+            partial: self.budget.is_spent(),
+        })
+    }
+
+    /// Returns the loser, relative to the mover, if the mover turns the
+    /// fragment into `next`.
+    fn play(&mut self, next: &[u8]) -> usize {
+        if self.is_word(next) {
+            0
+        } else {
+            (1 + self.value(next)) % self.players
+        }
+    }
+
+    /// Returns the loser, relative to the player to move, for a fragment
+    /// that is part of a word but not a word itself.  Once the budget has
+    /// run out, this is only a guess that the player to move loses.
+    fn value(&mut self, fragment: &[u8]) -> usize {
+        if let Some(&loser) = self.memo.get(fragment) {
+            return loser;
+        }
+        if self.budget.spend() {
+            return 0;
+        }
+        let outcomes = self
+            .moves(fragment)
+            .into_iter()
+            .map(|(_, _, next)| self.play(&next))
+            .collect::<Vec<_>>();
+        let loser = self.choose(outcomes.into_iter());
+        // Guesses further down would make this one a guess too.
+        if !self.budget.is_spent() {
+            self.memo.insert(fragment.to_owned(), loser);
+        }
+        loser
+    }
+
+    /// Picks the outcome the mover likes best: someone else losing, and as
+    /// soon as possible.
+    fn choose(&self, outcomes: impl Iterator<Item = usize>) -> usize {
+        outcomes
+            .min_by_key(|&loser| if loser == 0 { self.players } else { loser })
+            .unwrap_or(0)
+    }
+
+    fn is_long_enough(&self, idx: usize) -> bool {
+        self.words.num_letters(idx) >= self.min_len
+    }
+
+    /// Finds the places that `fragment` can be in a word long enough to
+    /// count, as a word index and tile offset.
+    fn occurrences(&self, fragment: &[u8]) -> Vec<(usize, usize)> {
+        match self.rules {
+            Rules::Ghost => self
+                .words
+                .with_prefix(fragment)
+                .filter(|&idx| self.is_long_enough(idx))
+                .map(|idx| (idx, 0))
+                .collect(),
+            Rules::Superghost => self
+                .words
+                .occurrences(fragment)
+                .filter(|&(idx, _)| self.is_long_enough(idx))
+                .collect(),
+        }
+    }
+
+    fn is_word(&self, fragment: &[u8]) -> bool {
+        self.words
+            .find(fragment)
+            .is_some_and(|idx| self.is_long_enough(idx))
+    }
+
+    /// Lists the distinct legal moves from `fragment`.
+    fn moves(&self, fragment: &[u8]) -> BTreeSet<(Side, u8, Vec<u8>)> {
+        let mut moves = BTreeSet::new();
+        for (idx, pos) in self.occurrences(fragment) {
+            let word = self.words.tiles(idx);
+            let end = pos + fragment.len();
+            if pos > 0 {
+                let tile = word[pos - 1];
+                moves.insert((Side::Front, tile, [&[tile], fragment].concat()));
+            }
+            if end < word.len() {
+                let tile = word[end];
+                moves.insert((Side::Back, tile, [fragment, &[tile]].concat()));
+            }
+        }
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+    use itertools::Itertools;
+
+    fn winning_letters(outcome: &Outcome) -> String {
+        outcome.winning_moves().map(|m| m.letter.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ghost_two_players() {
+        // After "ca", "cat" loses at once, and "cab" leads to "cabs".  "cab"
+        // forces the next player to complete "cabs", so it wins.
+        let lex = Lexicon::from_iter(["cat", "cabs", "carts"]);
+        let mut solver = Solver::new(&lex, Rules::Ghost, 2, 3).unwrap();
+        let outcome = solver.solve("ca").unwrap();
+        check!(outcome.moves.iter().map(|m| m.letter.as_str()).join("") == "brt");
+        check!(winning_letters(&outcome) == "b");
+        check!(outcome.is_winning());
+    }
+
+    #[test]
+    fn test_ghost_losing_position() {
+        let lex = Lexicon::from_iter(["cat", "cab"]);
+        let mut solver = Solver::new(&lex, Rules::Ghost, 2, 3).unwrap();
+        let outcome = solver.solve("ca").unwrap();
+        check!(!outcome.is_winning());
+        check!(winning_letters(&outcome) == "");
+    }
+
+    #[test]
+    fn test_min_length() {
+        // With a minimum of 4 letters, "cat" doesn't count, so the only way
+        // out of "ca" is via "cats".
+        let lex = Lexicon::from_iter(["cat", "cats"]);
+        let mut solver = Solver::new(&lex, Rules::Ghost, 2, 4).unwrap();
+        let outcome = solver.solve("ca").unwrap();
+        check!(outcome.is_winning());
+        check!(winning_letters(&outcome) == "t");
+    }
+
+    #[test]
+    fn test_three_players() {
+        // "ca" -> "cab" -> "cabs": the third player to move completes a word,
+        // so the player to move now is safe.
+        let lex = Lexicon::from_iter(["cabs", "cat"]);
+        let mut solver = Solver::new(&lex, Rules::Ghost, 3, 3).unwrap();
+        let outcome = solver.solve("c").unwrap();
+        check!(outcome.loser == 2);
+        check!(winning_letters(&outcome) == "a");
+    }
+
+    #[test]
+    fn test_superghost() {
+        // Under Superghost, "at" can become "cat", which loses at once, or
+        // "tat" and "ats", which force the next player to finish a word.
+        let lex = Lexicon::from_iter(["cat", "cats", "tats"]);
+        let mut solver = Solver::new(&lex, Rules::Superghost, 2, 3).unwrap();
+        let outcome = solver.solve("at").unwrap();
+        let moves = outcome
+            .moves
+            .iter()
+            .map(|m| m.fragment.as_str())
+            .collect_vec();
+        check!(moves == ["cat", "tat", "ats"]);
+        check!(winning_letters(&outcome) == "ts");
+    }
+
+    #[test]
+    fn test_spanish() {
+        // "ñ" and "ll" are tiles of their own, so they are single moves.
+        let spanish = "spanish".parse().unwrap();
+        let words = ["niño", "niños", "nieto", "nietos", "callo"];
+        let lex = Lexicon::new(words, spanish, Default::default());
+        let mut solver = Solver::new(&lex, Rules::Ghost, 2, 4).unwrap();
+        let outcome = solver.solve("ni").unwrap();
+        let moves = outcome
+            .moves
+            .iter()
+            .map(|m| m.fragment.as_str())
+            .collect_vec();
+        check!(moves == ["nie", "niñ"]);
+        check!(winning_letters(&outcome) == "ñ");
+        let outcome = solver.solve("ca").unwrap();
+        check!(outcome.moves.iter().map(|m| m.letter.as_str()).join(",") == "ll");
+    }
+
+    #[test]
+    fn test_budget() {
+        // "cab", "car" and "cart" are the positions to work out from "ca".
+        let lex = Lexicon::from_iter(["cat", "cabs", "carts"]);
+        let solve = |max_nodes, time_limit| {
+            let solver = Solver::new(&lex, Rules::Ghost, 2, 3).unwrap();
+            let mut solver = solver.with_limits(max_nodes, time_limit);
+            solver.solve("ca").unwrap()
+        };
+        let outcome = solve(Some(3), None);
+        check!(!outcome.partial);
+        check!(winning_letters(&outcome) == "b");
+        check!(solve(Some(2), None).partial);
+        check!(solve(None, Some(Duration::ZERO)).partial);
+    }
+
+    #[test]
+    fn test_errors() {
+        let lex = Lexicon::from_iter(["cat"]);
+        check!(Solver::new(&lex, Rules::Ghost, 1, 3).err() == Some(SolveError::TooFewPlayers));
+        let mut solver = Solver::new(&lex, Rules::Ghost, 2, 3).unwrap();
+        check!(solver.solve("cat").err() == Some(SolveError::AlreadyAWord("cat".to_owned())));
+        check!(solver.solve("x").err() == Some(SolveError::NotAFragment("x".to_owned())));
+    }
+}
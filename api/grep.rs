@@ -1,10 +1,99 @@
 use crate::lexi::Lexicon;
-use regex::RegexBuilder;
-
-pub fn search(spec: &super::GrepSpec) {
-    let re = RegexBuilder::new(&spec.regex).case_insensitive(spec.case_insensitive).build().unwrap();
-    let lexi = Lexicon::load();
-    lexi.entries().filter(|entry| re.is_match(entry.word())).for_each(|entry| {
-        println!("{}", entry.word());
-    });
+use owo_colors::OwoColorize;
+use regex::{Regex, RegexBuilder};
+use std::process;
+
+/// How a regex is matched against the words in a lexicon.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegexOptions {
+    pub case_insensitive: bool,
+    /// Anchor the regex at both ends, so that it has to match the whole
+    /// word rather than part of it.
+    pub whole: bool,
+    /// The most memory the compiled regex may use, if not the regex
+    /// crate's default.
+    pub size_limit: Option<usize>,
+}
+
+/// Compiles a regex for matching words, with an error message fit for
+/// showing to whoever typed it.
+pub fn build_regex(pattern: &str, options: RegexOptions) -> Result<Regex, String> {
+    let anchored;
+    let pattern = if options.whole {
+        anchored = format!("^(?:{pattern})$");
+        &anchored
+    } else {
+        pattern
+    };
+    let mut builder = RegexBuilder::new(pattern);
+    builder.case_insensitive(options.case_insensitive);
+    if let Some(limit) = options.size_limit {
+        builder.size_limit(limit);
+    }
+    builder.build().map_err(|e| match e {
+        regex::Error::CompiledTooBig(limit) => {
+            format!("Regex is too complicated (it needs more than {limit} bytes)")
+        }
+        e => format!("Invalid regex: {e}"),
+    })
+}
+
+pub fn search(spec: &super::GrepSpec, lexi: &Lexicon) {
+    let filter = match spec.to_filter(lexi) {
+        Ok(filter) => filter,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            process::exit(1);
+        }
+    };
+    spec.sort
+        .apply(lexi.matching_entries(&filter))
+        .for_each(|entry| {
+            println!("{}", entry.phrase());
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{check, let_assert};
+
+    #[test]
+    fn test_build_regex() {
+        let re = build_regex("c.t", RegexOptions::default()).unwrap();
+        check!(re.is_match("scatter"));
+
+        let whole = RegexOptions {
+            whole: true,
+            ..Default::default()
+        };
+        let re = build_regex("c.t|dog", whole).unwrap();
+        check!(re.is_match("cat"));
+        check!(re.is_match("dog"));
+        check!(!re.is_match("scatter"));
+        check!(!re.is_match("dogs"));
+
+        let re = build_regex(
+            "CAT",
+            RegexOptions {
+                case_insensitive: true,
+                ..whole
+            },
+        )
+        .unwrap();
+        check!(re.is_match("cat"));
+    }
+
+    #[test]
+    fn test_bad_regex() {
+        let_assert!(Err(e) = build_regex("ca(t", RegexOptions::default()));
+        check!(e.starts_with("Invalid regex"));
+
+        let limited = RegexOptions {
+            size_limit: Some(1000),
+            ..Default::default()
+        };
+        let_assert!(Err(e) = build_regex("\\w{50}", limited));
+        check!(e.starts_with("Regex is too complicated"));
+    }
 }
@@ -1,13 +1,51 @@
+mod affixes;
+mod alphabet;
+mod enumeration;
 mod filter;
+mod index;
 mod length_range;
+mod letter_count;
+mod loader;
+mod normalize;
+mod pattern;
+mod query;
+mod registry;
+mod shape;
+mod sort;
 mod sorted_letters;
+mod tile_words;
 
-pub use filter::{Filter, FilterBuilder};
+pub use alphabet::{Alphabet, AlphabetError};
+pub use enumeration::Enumeration;
+pub use filter::{Check, Filter, FilterBuilder};
+pub use index::IndexError;
 pub use length_range::LengthRange;
-pub use sorted_letters::SortedLetters;
-
+pub use letter_count::LetterCount;
+pub use loader::{LexiconConfig, LexiconSource, LoadError};
+pub use normalize::Accents;
+pub use pattern::Pattern;
+pub use registry::Lexicons;
+pub use shape::{Shape, Vowels};
+pub use sort::{Sort, SortKey, SortOrder};
+pub use sorted_letters::{SortedLetters, BLANK};
+pub use tile_words::TileWords;
+
+use crate::parallel;
+use affixes::AffixIndex;
+use itertools::Either;
 use serde_repr::*;
+use std::borrow::Cow;
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Popular words with a rank below this are rated as very popular.
+pub const DEFAULT_POPULAR_THRESHOLD: usize = 10000;
+
+/// The best anagram of some letters is called very popular if its rank is
+/// below this.
+pub const DEFAULT_ANAGRAM_THRESHOLD: usize = 1500;
 
 /// A lexicon is a collection of words plus popularity data.
 pub struct Lexicon<'a> {
@@ -16,85 +54,236 @@ pub struct Lexicon<'a> {
     // Index of entries by their sorted letters.  This greatly speeds
     // up the inner loop of SearchService::anagram_breakdowns().
     from_sorted: HashMap<SortedLetters, Vec<usize>>,
+    // Index of entries by their number of letters, so that a filter for
+    // one length doesn't have to look at every word.
+    by_length: Vec<Vec<usize>>,
+    // Indexes for prefix, suffix and substring searches, built the first
+    // time they're needed.
+    affixes: OnceLock<AffixIndex>,
+    // The single words split into tiles, for Ghost, built the first time
+    // they're needed.
+    tile_words: OnceLock<TileWords>,
 
     /// The maximum rank for an entry to be considered "very popular", rather
     /// than "less popular".
     popular_threshold: usize,
+
+    /// The maximum rank for the best anagram of some letters to count as
+    /// "very popular" in `solve_anagram()`.
+    anagram_threshold: usize,
+
+    /// The tiles that words are made from.
+    alphabet: Alphabet,
+
+    /// Whether accented letters are folded into plain ones.
+    accents: Accents,
 }
 
 impl<'a> Lexicon<'a> {
+    /// Makes a lexicon from a list of words, splitting them into the tiles
+    /// of `alphabet` and treating accented letters as `accents` says.
+    pub fn new(
+        words: impl IntoIterator<Item = &'a str>,
+        alphabet: Alphabet,
+        accents: Accents,
+    ) -> Self {
+        let entries = words
+            .into_iter()
+            .map(|word| Entry::with_alphabet(word, &alphabet, accents))
+            .collect::<Vec<_>>();
+        Lexicon {
+            from_sorted: index_by_letters(&entries),
+            by_length: index_by_length(&entries),
+            affixes: OnceLock::new(),
+            tile_words: OnceLock::new(),
+            entries,
+            popular_threshold: DEFAULT_POPULAR_THRESHOLD,
+            anagram_threshold: DEFAULT_ANAGRAM_THRESHOLD,
+            alphabet,
+            accents,
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.entries.len()
     }
 
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn accents(&self) -> Accents {
+        self.accents
+    }
+
+    /// The form of some text that the lexicon's letter checks see, with
+    /// accents folded if the lexicon folds them.
+    pub fn normalize<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        self.alphabet.normalize(text, self.accents)
+    }
+
+    /// The form of some text that prefix, suffix and substring checks look
+    /// for in entries, which is normalized and has only letters.
+    pub fn search_text<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        letters_only(self.normalize(text))
+    }
+
+    /// Reads a rack of letters typed by a user, with `?` for blanks.
+    /// Accents are folded if the lexicon folds them, and anything that
+    /// isn't on one of the alphabet's tiles is an error.
+    pub fn parse_letters(&self, text: &str) -> Result<SortedLetters, String> {
+        let normalized = self.normalize(text);
+        if let Some(ch) = normalized
+            .chars()
+            .find(|&ch| !ch.is_alphabetic() && ch != BLANK && !ch.is_whitespace())
+        {
+            return Err(not_a_tile(ch));
+        }
+        self.alphabet.letters(&normalized).map_err(not_a_tile)
+    }
+
+    /// Reads a set of letters typed by a user, such as the letters that
+    /// words must include, the same way as `parse_letters`.
+    pub fn parse_mask(&self, text: &str) -> Result<LetterMask, String> {
+        let normalized = self.normalize(text);
+        if let Some(ch) = normalized
+            .chars()
+            .find(|&ch| !ch.is_alphabetic() && !ch.is_whitespace())
+        {
+            return Err(not_a_tile(ch));
+        }
+        self.alphabet.mask(&normalized).map_err(not_a_tile)
+    }
+
+    /// Adds popularity data from a list of words, most popular first.
+    /// Each line may also give a frequency count after a tab, as in
+    /// `word<TAB>count`, in which case the list is ranked by count.  Without
+    /// counts, frequencies are estimated from rank using Zipf's law.
     pub fn with_popular_words(
         mut self,
         popular_words: impl IntoIterator<Item = &'a str>,
         threshold: usize,
     ) -> Self {
-        let popularity = popular_words
+        let mut popular = popular_words
             .into_iter()
-            .enumerate()
-            .map(|(rank, word)| (word, rank))
-            .collect::<HashMap<_, _>>();
+            .map(parse_frequency)
+            .collect::<Vec<_>>();
+        if popular.iter().any(|(_, count)| count.is_some()) {
+            popular.sort_by_key(|&(_, count)| Reverse(count.unwrap_or(0)));
+        }
+
+        let num_popular = popular.len() as u64;
+        let mut popularity = HashMap::with_capacity(popular.len());
+        for (rank, (word, count)) in popular.into_iter().enumerate() {
+            let frequency = count.unwrap_or_else(|| (num_popular / (rank as u64 + 1)).max(1));
+            popularity.entry(word).or_insert((rank, frequency));
+        }
 
         for entry in &mut self.entries {
-            entry.rank = popularity.get(&entry.word).copied();
+            let popularity = popularity.get(&entry.word);
+            entry.rank = popularity.map(|&(rank, _)| rank);
+            entry.frequency = popularity.map(|&(_, frequency)| frequency);
         }
         self.popular_threshold = threshold;
+        self.rate_entries();
 
         self
     }
 
-    pub fn rate(&self, entry: &Entry<'_>) -> Popularity {
-        match entry.rank {
-            Some(rank) => {
-                if rank < self.popular_threshold {
-                    Popularity::High
-                } else {
-                    Popularity::Medium
-                }
-            }
-            None => Popularity::Low,
+    /// Overrides the thresholds for rating popularity.
+    pub fn with_thresholds(mut self, popular: Option<usize>, anagram: Option<usize>) -> Self {
+        if let Some(popular) = popular {
+            self.popular_threshold = popular;
         }
+        if let Some(anagram) = anagram {
+            self.anagram_threshold = anagram;
+        }
+        self.rate_entries();
+        self
     }
-}
 
-impl Lexicon<'static> {
-    /// Load the default lexicon.
-    pub fn load() -> Self {
-        const WORDS: &str = include_str!("../lexicon/twl06.txt");
-        const POPULAR_WORDS: &str = include_str!("../lexicon/tv2006.txt");
+    pub fn rate(&self, entry: &Entry<'_>) -> Popularity {
+        entry.popularity
+    }
 
-        Lexicon::from_iter(WORDS.lines()).with_popular_words(POPULAR_WORDS.lines(), 10000)
+    /// Works out each entry's popularity from its rank, so that filters can
+    /// check it without the lexicon.  This has to be redone whenever the
+    /// ranks or the threshold change.
+    fn rate_entries(&mut self) {
+        for entry in &mut self.entries {
+            entry.popularity = match entry.rank {
+                Some(rank) if rank < self.popular_threshold => Popularity::High,
+                Some(_) => Popularity::Medium,
+                None => Popularity::Low,
+            };
+        }
     }
 }
 
 impl<'a> FromIterator<&'a str> for Lexicon<'a> {
     fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
-        let mut entries = Vec::new();
-        let mut from_sorted: HashMap<SortedLetters, Vec<usize>> = HashMap::new();
-        for (idx, word) in iter.into_iter().enumerate() {
-            let entry = Entry::new(word);
+        Lexicon::new(iter, Alphabet::default(), Accents::default())
+    }
+}
+
+/// Leaves out anything that isn't a letter, so that searches ignore the
+/// spaces and punctuation in phrases.
+fn letters_only(text: Cow<'_, str>) -> Cow<'_, str> {
+    if text.chars().all(char::is_alphabetic) {
+        return text;
+    }
+    Cow::Owned(text.chars().filter(|ch| ch.is_alphabetic()).collect())
+}
+
+fn not_a_tile(ch: char) -> String {
+    format!("{ch:?} is not a letter that can be on a tile")
+}
+
+/// Groups entries by their letters.  Entries with letters that aren't on
+/// any tile are left out, because no rack can make them.
+fn index_by_letters(entries: &[Entry]) -> HashMap<SortedLetters, Vec<usize>> {
+    let mut from_sorted: HashMap<SortedLetters, Vec<usize>> = HashMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        if entry.in_alphabet {
             from_sorted.entry(entry.sorted).or_default().push(idx);
-            entries.push(entry);
         }
-        Lexicon {
-            from_sorted,
-            entries,
-            popular_threshold: 0,
+    }
+    from_sorted
+}
+
+fn index_by_length(entries: &[Entry]) -> Vec<Vec<usize>> {
+    let mut by_length: Vec<Vec<usize>> = Vec::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        if by_length.len() <= entry.len {
+            by_length.resize_with(entry.len + 1, Vec::new);
         }
+        by_length[entry.len].push(idx);
+    }
+    by_length
+}
+
+/// Splits a `word<TAB>count` line into its parts.  The count is optional.
+fn parse_frequency(line: &str) -> (&str, Option<u64>) {
+    match line.split_once('\t') {
+        Some((word, count)) => (word.trim(), count.trim().parse().ok()),
+        None => (line.trim(), None),
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Entry<'a> {
     word: &'a str,
+    text: Cow<'a, str>,
+    in_alphabet: bool,
     rank: Option<usize>,
+    frequency: Option<u64>,
+    popularity: Popularity,
     mask: LetterMask,
     sorted: SortedLetters,
     len: usize,
     one_word: bool,
+    enumeration: Enumeration,
+    vowels: Vowels,
 }
 
 pub struct Anagrams<'a> {
@@ -121,8 +310,21 @@ impl<'a> Lexicon<'a> {
         self.entries.iter()
     }
 
+    /// Finds the words that use exactly these letters.  Blanks can stand
+    /// for any letter, but need a scan of the whole lexicon.
     pub fn solve_anagram(&'a self, letters: &SortedLetters) -> Anagrams<'a> {
-        let indexes = self.from_sorted.get(letters).cloned().unwrap_or_default();
+        let indexes = if letters.num_blanks() == 0 {
+            self.from_sorted.get(letters).cloned().unwrap_or_default()
+        } else {
+            (0..self.entries.len())
+                .filter(|&idx| {
+                    let entry = &self.entries[idx];
+                    entry.in_alphabet
+                        && entry.sorted.len() == letters.len()
+                        && entry.sorted.is_subset(letters)
+                })
+                .collect()
+        };
         Anagrams {
             entries: &self.entries,
             indexes,
@@ -130,31 +332,147 @@ impl<'a> Lexicon<'a> {
         }
     }
 
+    /// True if the word is in the lexicon.
+    pub fn contains(&'a self, word: &str) -> bool {
+        let text = self.normalize(word);
+        let letters = match self.alphabet.letters(&text) {
+            Ok(letters) if text.chars().all(char::is_alphabetic) => letters,
+            _ => return self.entries.iter().any(|entry| entry.word == word),
+        };
+        self.solve_anagram(&letters)
+            .any(|entry| entry.word() == word)
+    }
+
+    /// Finds the entry for a word or phrase, ignoring case and spacing, and
+    /// accents if the lexicon folds them.
+    pub fn find(&'a self, word: &str) -> Option<&'a Entry<'a>> {
+        let lower = word.to_lowercase();
+        let text = self.search_text(&lower);
+        match self.alphabet.letters(&text) {
+            Ok(letters) => self
+                .solve_anagram(&letters)
+                .find(|entry| entry.text == text),
+            Err(_) => self.entries.iter().find(|entry| entry.text == text),
+        }
+    }
+
+    /// Finds the entries that match the filter, in lexicon order.  If the
+    /// filter needs a prefix, suffix or substring, or only allows one
+    /// length, only the words that an index says might match are checked.
+    pub fn matching_entries(
+        &'a self,
+        filter: &'a Filter,
+    ) -> impl Iterator<Item = &'a Entry<'a>> + 'a {
+        let filter = filter.normalized(self);
+        let candidates = match self.candidates(&filter) {
+            Some(indexes) => {
+                Either::Left((0..indexes.len()).map(move |i| &self.entries[indexes[i]]))
+            }
+            None => Either::Right(self.entries.iter()),
+        };
+        candidates.filter(move |entry| filter.matches(entry))
+    }
+
+    /// Finds the same entries as `matching_entries`, in the same order, but
+    /// checks them on up to `threads` threads.
+    pub fn par_matching_entries(&'a self, filter: &Filter, threads: usize) -> Vec<&'a Entry<'a>> {
+        let filter = filter.normalized(self);
+        match self.candidates(&filter) {
+            Some(indexes) => {
+                parallel::filter(&indexes, threads, |&idx| filter.matches(&self.entries[idx]))
+                    .into_iter()
+                    .map(|&idx| &self.entries[idx])
+                    .collect()
+            }
+            None => parallel::filter(&self.entries, threads, |entry| filter.matches(entry)),
+        }
+    }
+
+    /// Uses an index to narrow down the entries that might match the
+    /// filter, if any of its checks allow it.
+    fn candidates(&self, filter: &Filter) -> Option<Cow<'_, [usize]>> {
+        for check in filter.checks() {
+            let found = match check {
+                Check::Prefix(prefix) => self.affixes().with_prefix(&self.entries, prefix),
+                Check::Suffix(suffix) => self.affixes().with_suffix(&self.entries, suffix),
+                Check::Substring(needle) => self.affixes().with_substring(&self.entries, needle),
+                _ => continue,
+            };
+            return Some(Cow::Owned(found));
+        }
+        let len = filter.exact_length()?;
+        Some(match self.by_length.get(len) {
+            Some(indexes) => Cow::Borrowed(indexes),
+            None => Cow::Owned(Vec::new()),
+        })
+    }
+
+    fn affixes(&self) -> &AffixIndex {
+        self.affixes.get_or_init(|| AffixIndex::new(&self.entries))
+    }
+
+    /// The single words that can be spelled with the alphabet's tiles, split
+    /// into tiles.
+    pub fn tile_words(&self) -> &TileWords {
+        self.tile_words
+            .get_or_init(|| TileWords::new(&self.entries, &self.alphabet))
+    }
+
     #[allow(dead_code)]
     pub fn matching_words(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a str> + 'a {
-        self.entries()
-            .filter(|entry| filter.matches(entry))
-            .map(|e| e.word)
+        self.matching_entries(filter).map(|e| e.word)
     }
 }
 
 impl<'a> Entry<'a> {
+    #[allow(dead_code)]
     pub fn new(word: &'a str) -> Self {
-        let one_word = word.chars().all(|ch| ch.is_ascii_alphabetic());
-        let letters = word
-            .chars()
-            .filter(char::is_ascii_alphabetic)
-            .collect::<String>();
-        let mask = LetterMask::new(&letters);
-        let sorted = SortedLetters::from_word(&letters);
-        let len = word.chars().filter(|ch| ch.is_alphabetic()).count();
+        Self::with_alphabet(word, &Alphabet::english(), Accents::default())
+    }
+
+    /// Works out the tiles in a word or phrase, which may be followed by
+    /// its enumeration, as in `ice cream (3,5)`.  Everything except the
+    /// word itself is based on its normalized text, so with folded accents
+    /// "café" has the letters of "cafe".
+    pub fn with_alphabet(line: &'a str, alphabet: &Alphabet, accents: Accents) -> Self {
+        let (word, given) = Enumeration::split_line(line);
+        let normalized = alphabet.normalize(word, accents);
+        let enumeration = Enumeration::of(&normalized);
+        let text = letters_only(normalized);
+        let len = text.chars().count();
+        let enumeration = given
+            .filter(|given| given.num_letters() == len)
+            .unwrap_or(enumeration);
+        let one_word = enumeration.len() == 1 && word.chars().all(char::is_alphabetic);
+        let (mask, sorted, in_alphabet) = if alphabet.is_english() {
+            let in_alphabet = text
+                .chars()
+                .all(|ch| ch.is_ascii_alphabetic() || !ch.is_alphabetic());
+            (
+                LetterMask::new(&text),
+                SortedLetters::from_word(&text),
+                in_alphabet,
+            )
+        } else {
+            match (alphabet.mask(&text), alphabet.letters(&text)) {
+                (Ok(mask), Ok(sorted)) => (mask, sorted.with_blanks(0), true),
+                _ => (LetterMask::default(), SortedLetters::default(), false),
+            }
+        };
+        let vowels = Vowels::new(&text);
         Self {
             word,
+            text,
+            in_alphabet,
             rank: None,
+            frequency: None,
+            popularity: Popularity::Low,
             mask,
             sorted,
             len,
             one_word,
+            enumeration,
+            vowels,
         }
     }
 
@@ -176,27 +494,83 @@ impl<'a> Entry<'a> {
         self.word
     }
 
+    /// The word or phrase with its spaces and hyphens, as given by its
+    /// enumeration when the word list doesn't spell them out.
+    pub fn phrase(&self) -> Cow<'a, str> {
+        if Enumeration::of(self.word).separators() == self.enumeration.separators() {
+            Cow::Borrowed(self.word)
+        } else {
+            Cow::Owned(self.enumeration.spell(self.word))
+        }
+    }
+
+    /// The letters of the word as letter checks see them, with accents
+    /// folded if the lexicon folds them, and without any spaces or
+    /// punctuation.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn enumeration(&self) -> &Enumeration {
+        &self.enumeration
+    }
+
+    /// False if the word has letters that aren't on any tile, so it can't
+    /// be made from a rack.
+    pub fn is_in_alphabet(&self) -> bool {
+        self.in_alphabet
+    }
+
     pub fn without_letters_in(&self, sorted: &SortedLetters) -> Option<SortedLetters> {
         self.sorted.minus(sorted)
     }
 
+    /// Works out which letters of this word have to come from the blanks in
+    /// a rack, or returns None if the word can't be made from the rack.
+    pub fn blanks_from(&self, rack: &SortedLetters) -> Option<SortedLetters> {
+        if !self.in_alphabet {
+            return None;
+        }
+        rack.minus_with_blanks(&self.sorted)
+            .map(|(_, blanks)| blanks)
+    }
+
     pub fn rank(&self) -> Option<usize> {
         self.rank
     }
 
+    /// How often the word is used, if it's in the popularity list at all.
+    /// Higher is more popular.
+    pub fn frequency(&self) -> Option<u64> {
+        self.frequency
+    }
+
     pub(crate) fn letters(&self) -> &SortedLetters {
         &self.sorted
     }
+
+    pub fn vowels(&self) -> &Vowels {
+        &self.vowels
+    }
+
+    /// The number of letters that aren't vowels.
+    pub fn num_consonants(&self, y_is_vowel: bool) -> usize {
+        self.len - self.vowels.count(y_is_vowel)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The set of tiles in a word, one bit per tile of its alphabet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct LetterMask(u32);
 
 impl LetterMask {
+    /// Collects the letters A to Z, as tiles of the English alphabet.
+    /// Anything else is ignored, so accents have to be folded first if
+    /// they should count.
     pub fn new(letters: &str) -> Self {
         let mut mask = 0;
         for ch in letters.chars() {
-            if ch.is_alphabetic() {
+            if ch.is_ascii_alphabetic() {
                 mask |= 1 << (ch.to_ascii_lowercase() as usize - 'a' as usize);
             }
         }
@@ -215,6 +589,13 @@ impl TryFrom<&str> for LetterMask {
     }
 }
 
+impl TryFrom<Option<LetterMask>> for LetterMask {
+    type Error = ();
+    fn try_from(mask: Option<LetterMask>) -> Result<Self, Self::Error> {
+        mask.ok_or(())
+    }
+}
+
 impl TryFrom<Option<&str>> for LetterMask {
     type Error = ();
     fn try_from(s: Option<&str>) -> Result<Self, Self::Error> {
@@ -236,16 +617,16 @@ pub fn solve_anagram(lexi: &Lexicon, letters: &SortedLetters) -> RankedWord {
 
     match result {
         Some(entry) => {
-            let word = entry.word().to_owned();
+            let word = entry.phrase().into_owned();
             let quality = match entry.rank() {
-                Some(rank) if rank < 1500 => Quality::VeryPopular,
+                Some(rank) if rank < lexi.anagram_threshold => Quality::VeryPopular,
                 Some(_) => Quality::LessPopular,
                 None => Quality::NotPopular,
             };
             RankedWord { word, quality }
         }
         None => RankedWord {
-            word: letters.to_string(),
+            word: lexi.alphabet.spell(letters),
             quality: Quality::NotWord,
         },
     }
@@ -271,3 +652,171 @@ pub enum Popularity {
     Medium = 2,
     High = 3,
 }
+
+/// Parses `low`, `medium` or `high`, or the ratings 1 to 3.
+impl FromStr for Popularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" | "1" => Ok(Popularity::Low),
+            "medium" | "2" => Ok(Popularity::Medium),
+            "high" | "3" => Ok(Popularity::High),
+            _ => Err(format!(
+                "Invalid popularity: {s} (expected low, medium or high)"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    fn ranks(lex: &Lexicon) -> Vec<(Option<usize>, Option<u64>)> {
+        lex.entries().map(|e| (e.rank(), e.frequency())).collect()
+    }
+
+    #[test]
+    fn test_popular_words_by_rank() {
+        let lex = Lexicon::from_iter(["cat", "dog", "gnu", "yak"])
+            .with_popular_words(["dog", "cat", "the", "gnu"], 2);
+        check!(
+            ranks(&lex)
+                == [
+                    (Some(1), Some(2)),
+                    (Some(0), Some(4)),
+                    (Some(3), Some(1)),
+                    (None, None)
+                ]
+        );
+        check!(lex.rate(lex.entries().next().unwrap()) == Popularity::High);
+        check!(lex.rate(lex.entries().nth(2).unwrap()) == Popularity::Medium);
+    }
+
+    #[test]
+    fn test_popular_words_with_counts() {
+        let lex = Lexicon::from_iter(["cat", "dog", "gnu"])
+            .with_popular_words(["gnu\t5", "dog\t700", "cat\t90"], 1);
+        check!(
+            ranks(&lex)
+                == [
+                    (Some(1), Some(90)),
+                    (Some(0), Some(700)),
+                    (Some(2), Some(5))
+                ]
+        );
+    }
+
+    #[test]
+    fn test_par_matching_entries() {
+        let letter = |i: usize| (b'a' + (i % 26) as u8) as char;
+        let words = (0..5000)
+            .map(|i| {
+                [i, i / 26, i / 676]
+                    .into_iter()
+                    .map(letter)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        let lex = Lexicon::from_iter(words.iter().map(String::as_str));
+        let filters = [
+            FilterBuilder::new().substring(Some("q")).build(),
+            FilterBuilder::new()
+                .include_letters(LetterMask::new("xy"))
+                .build(),
+            FilterBuilder::new()
+                .prefix(Some("ab"))
+                .length(Some("3"))
+                .build(),
+        ];
+        for filter in &filters {
+            let expected = lex
+                .matching_entries(filter)
+                .map(Entry::word)
+                .collect::<Vec<_>>();
+            check!(!expected.is_empty());
+            check!(expected.len() < lex.len());
+            for threads in [1, 3, 8] {
+                let found = lex.par_matching_entries(filter, threads);
+                check!(found.into_iter().map(Entry::word).collect::<Vec<_>>() == expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blanks() {
+        let lex = Lexicon::from_iter(["stone", "notes", "tonne", "tones"]);
+        let rack = SortedLetters::from_word("ton?e");
+        let words = lex
+            .solve_anagram(&rack)
+            .map(|e| e.word())
+            .collect::<Vec<_>>();
+        check!(words == ["stone", "notes", "tonne", "tones"]);
+        let entry = lex.entries().nth(2).unwrap();
+        check!(entry.blanks_from(&rack).unwrap().to_string() == "n");
+        check!(entry
+            .blanks_from(&SortedLetters::from_word("tone"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_accents() {
+        check!(LetterMask::new("éßα") == LetterMask(0));
+
+        let folded = Lexicon::new(["café", "straße"], Alphabet::default(), Accents::Fold);
+        let entries = folded.entries().collect::<Vec<_>>();
+        check!(entries[1].len() == 7);
+        check!(folded.parse_letters("CAFÉ") == Ok(SortedLetters::from_word("cafe")));
+        check!(folded.parse_letters("caf3").is_err());
+        let anagrams = folded.solve_anagram(&SortedLetters::from_word("efac"));
+        check!(anagrams.map(|e| e.word()).collect::<Vec<_>>() == ["café"]);
+        check!(folded.contains("café"));
+
+        let distinct = Lexicon::new(["café", "straße"], Alphabet::default(), Accents::Distinct);
+        check!(distinct.parse_letters("café").is_err());
+        check!(
+            distinct
+                .solve_anagram(&SortedLetters::from_word("efac"))
+                .count()
+                == 0
+        );
+        check!(distinct.contains("café"));
+        check!(!distinct.entries().next().unwrap().is_in_alphabet());
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let spanish = "spanish".parse::<Alphabet>().unwrap();
+        let lex = Lexicon::new(["chorro", "año", "llamé"], spanish, Accents::Fold);
+        let entries = lex.entries().collect::<Vec<_>>();
+        check!(entries[0].len() == 6);
+        check!(entries[0].letters().len() == 4);
+        check!(entries[1].text() == "año");
+        check!(entries[2].text() == "llame");
+
+        let rack = lex.parse_letters("RR O O ?").unwrap();
+        let words = lex
+            .solve_anagram(&rack)
+            .map(|e| e.word())
+            .collect::<Vec<_>>();
+        check!(words == ["chorro"]);
+        let blanks = entries[0].blanks_from(&rack).unwrap();
+        check!(lex.alphabet().spell(&blanks) == "ch");
+        check!(lex.parse_letters("ano") != lex.parse_letters("año"));
+        check!(lex.parse_letters("ç") == lex.parse_letters("c"));
+        check!(lex.parse_letters("α").is_err());
+        check!(lex.contains("año"));
+        check!(solve_anagram(&lex, &lex.parse_letters("llxy").unwrap()).word == "llxy");
+    }
+
+    #[test]
+    fn test_anagram_threshold() {
+        let lex = Lexicon::from_iter(["cat", "act"]).with_popular_words(["the", "act"], 10);
+        let letters = SortedLetters::from_word("tac");
+        check!(solve_anagram(&lex, &letters).quality == Quality::VeryPopular);
+        let lex = lex.with_thresholds(None, Some(1));
+        check!(solve_anagram(&lex, &letters).quality == Quality::LessPopular);
+    }
+}
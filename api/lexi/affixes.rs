@@ -0,0 +1,110 @@
+use super::Entry;
+
+/// Indexes for finding words by prefix, suffix or substring without
+/// looking at every entry.  Each list holds entry indexes, sorted so that
+/// the words with a given prefix (or suffix, or substring) are together.
+#[derive(Debug)]
+pub(super) struct AffixIndex {
+    // Entries sorted by word.
+    by_word: Vec<u32>,
+    // Entries sorted by word spelled backwards.
+    by_reversed: Vec<u32>,
+    // Every suffix of every word, as an entry index and byte offset,
+    // sorted by the text of the suffix.
+    suffixes: Vec<(u32, u16)>,
+}
+
+impl AffixIndex {
+    pub(super) fn new(entries: &[Entry]) -> Self {
+        let word = |idx: &u32| entries[*idx as usize].text();
+
+        let mut by_word = (0..entries.len() as u32).collect::<Vec<_>>();
+        by_word.sort_by_key(word);
+
+        let mut by_reversed = by_word.clone();
+        by_reversed.sort_by(|a, b| word(a).chars().rev().cmp(word(b).chars().rev()));
+
+        let mut suffixes = entries
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, entry)| {
+                entry
+                    .text()
+                    .char_indices()
+                    .map(move |(offset, _)| (idx as u32, offset as u16))
+            })
+            .collect::<Vec<_>>();
+        suffixes.sort_by_key(|&(idx, offset)| &entries[idx as usize].text()[offset as usize..]);
+
+        Self {
+            by_word,
+            by_reversed,
+            suffixes,
+        }
+    }
+
+    /// Finds the entries whose words start with `prefix`, in lexicon order.
+    pub(super) fn with_prefix(&self, entries: &[Entry], prefix: &str) -> Vec<usize> {
+        let word = |idx: &u32| entries[*idx as usize].text();
+        let start = self.by_word.partition_point(|idx| word(idx) < prefix);
+        let len = self.by_word[start..].partition_point(|idx| word(idx).starts_with(prefix));
+        in_order(self.by_word[start..start + len].iter().copied())
+    }
+
+    /// Finds the entries whose words end with `suffix`, in lexicon order.
+    pub(super) fn with_suffix(&self, entries: &[Entry], suffix: &str) -> Vec<usize> {
+        let word = |idx: &u32| entries[*idx as usize].text();
+        let start = self
+            .by_reversed
+            .partition_point(|idx| word(idx).chars().rev().lt(suffix.chars().rev()));
+        let len = self.by_reversed[start..].partition_point(|idx| word(idx).ends_with(suffix));
+        in_order(self.by_reversed[start..start + len].iter().copied())
+    }
+
+    /// Finds the entries whose words contain `needle`, in lexicon order.
+    pub(super) fn with_substring(&self, entries: &[Entry], needle: &str) -> Vec<usize> {
+        let suffix = |&(idx, offset): &(u32, u16)| &entries[idx as usize].text()[offset as usize..];
+        let start = self.suffixes.partition_point(|s| suffix(s) < needle);
+        let len = self.suffixes[start..].partition_point(|s| suffix(s).starts_with(needle));
+        let mut found = in_order(
+            self.suffixes[start..start + len]
+                .iter()
+                .map(|&(idx, _)| idx),
+        );
+        found.dedup();
+        found
+    }
+}
+
+fn in_order(indexes: impl Iterator<Item = u32>) -> Vec<usize> {
+    let mut indexes = indexes.map(|idx| idx as usize).collect::<Vec<_>>();
+    indexes.sort_unstable();
+    indexes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexi::Lexicon;
+    use assert2::check;
+
+    fn words<'a>(lex: &'a Lexicon, indexes: Vec<usize>) -> Vec<&'a str> {
+        let entries = lex.entries().collect::<Vec<_>>();
+        indexes.into_iter().map(|idx| entries[idx].word()).collect()
+    }
+
+    #[test]
+    fn test_affixes() {
+        let lex = Lexicon::from_iter([
+            "undo", "quaking", "unquiet", "sing", "un", "bun", "aqua", "running",
+        ]);
+        let index = super::AffixIndex::new(&lex.entries);
+        let entries = &lex.entries;
+
+        check!(words(&lex, index.with_prefix(entries, "un")) == ["undo", "unquiet", "un"]);
+        check!(words(&lex, index.with_prefix(entries, "x")).is_empty());
+        check!(words(&lex, index.with_suffix(entries, "ing")) == ["quaking", "sing", "running"]);
+        check!(words(&lex, index.with_suffix(entries, "un")) == ["un", "bun"]);
+        check!(words(&lex, index.with_substring(entries, "qu")) == ["quaking", "unquiet", "aqua"]);
+        check!(words(&lex, index.with_substring(entries, "n")).len() == 7);
+    }
+}
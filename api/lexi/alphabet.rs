@@ -0,0 +1,283 @@
+use super::normalize::fold_except;
+use super::{Accents, LetterMask, SortedLetters, BLANK};
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Letter bags and masks have room for this many different tiles.
+pub const MAX_TILES: usize = 32;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AlphabetError {
+    #[error("an alphabet needs at least one tile")]
+    Empty,
+    #[error("an alphabet can have at most {MAX_TILES} tiles, not {0}")]
+    TooManyTiles(usize),
+    #[error("tile {0:?} must be made of letters")]
+    BadTile(String),
+    #[error("tile {0:?} appears twice")]
+    Duplicate(String),
+}
+
+/// The set of tiles that words are made from, such as A to Z, or a
+/// Spanish set where CH, LL and RR are single tiles.  A tile's position in
+/// the alphabet is its index in `SortedLetters` and `LetterMask`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    tiles: Vec<String>,
+    /// True if the tiles are exactly A to Z, in order.
+    english: bool,
+    /// The letters on tiles that aren't A to Z, which folding accents
+    /// has to leave alone.
+    accented: Vec<char>,
+}
+
+const SPANISH: &str = "a b c ch d e f g h i j k l ll m n ñ o p q r rr s t u v w x y z";
+const WELSH: &str = "a b c ch d dd e f ff g ng h i j l ll m n o p ph r rh s t th u w y";
+
+impl Alphabet {
+    /// The letters A to Z, which is also how `SortedLetters::from_word`
+    /// reads letters.
+    pub fn english() -> Self {
+        Self {
+            tiles: ('a'..='z').map(String::from).collect(),
+            english: true,
+            accented: Vec::new(),
+        }
+    }
+
+    /// Makes an alphabet from a list of tiles, each one or more letters.
+    pub fn new<'s>(tiles: impl IntoIterator<Item = &'s str>) -> Result<Self, AlphabetError> {
+        let mut list: Vec<String> = Vec::new();
+        for tile in tiles {
+            let tile = tile.to_lowercase();
+            if tile.is_empty() || !tile.chars().all(char::is_alphabetic) {
+                return Err(AlphabetError::BadTile(tile));
+            }
+            if list.contains(&tile) {
+                return Err(AlphabetError::Duplicate(tile));
+            }
+            list.push(tile);
+        }
+        if list.is_empty() {
+            return Err(AlphabetError::Empty);
+        }
+        if list.len() > MAX_TILES {
+            return Err(AlphabetError::TooManyTiles(list.len()));
+        }
+        let english = list.iter().map(String::as_str).eq(Self::english().tiles());
+        let mut accented = list
+            .iter()
+            .flat_map(|tile| tile.chars())
+            .filter(|ch| !ch.is_ascii())
+            .collect::<Vec<_>>();
+        accented.sort_unstable();
+        accented.dedup();
+        Ok(Self {
+            tiles: list,
+            english,
+            accented,
+        })
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = &str> {
+        self.tiles.iter().map(String::as_str)
+    }
+
+    /// The letters on the tile at `idx`.
+    pub fn tile(&self, idx: usize) -> &str {
+        &self.tiles[idx]
+    }
+
+    pub fn is_english(&self) -> bool {
+        self.english
+    }
+
+    /// The form of `text` that tiles are read from.  Folding accents keeps
+    /// any letters that have tiles of their own, such as Spanish `ñ`.
+    pub fn normalize<'a>(&self, text: &'a str, accents: Accents) -> Cow<'a, str> {
+        match accents {
+            Accents::Fold => fold_except(text, &self.accented),
+            Accents::Distinct => Cow::Borrowed(text),
+        }
+    }
+
+    /// Splits a word into tiles, taking the longest tile that fits at each
+    /// point, so "chorro" is CH O RR O in Spanish.  Anything that isn't a
+    /// letter is skipped.  Fails with the first letter that doesn't start
+    /// any tile.
+    pub fn split(&self, text: &str) -> Result<Vec<usize>, char> {
+        let text = text.to_lowercase();
+        let mut rest = text.as_str();
+        let mut tiles = Vec::with_capacity(text.len());
+        while let Some(ch) = rest.chars().next() {
+            if !ch.is_alphabetic() {
+                rest = &rest[ch.len_utf8()..];
+                continue;
+            }
+            let (idx, tile) = self
+                .tiles
+                .iter()
+                .enumerate()
+                .filter(|(_, tile)| rest.starts_with(tile.as_str()))
+                .max_by_key(|(_, tile)| tile.len())
+                .ok_or(ch)?;
+            tiles.push(idx);
+            rest = &rest[tile.len()..];
+        }
+        Ok(tiles)
+    }
+
+    /// Counts the tiles in some letters, with `?` for blanks.
+    pub fn letters(&self, text: &str) -> Result<SortedLetters, char> {
+        if self.english {
+            if let Some(ch) = text
+                .chars()
+                .find(|&ch| ch.is_alphabetic() && !ch.is_ascii_alphabetic())
+            {
+                return Err(ch);
+            }
+            return Ok(SortedLetters::from_word(text));
+        }
+        let mut counts = [0; MAX_TILES];
+        for idx in self.split(text)? {
+            counts[idx] += 1;
+        }
+        let blanks = text.chars().filter(|&ch| ch == BLANK).count();
+        Ok(SortedLetters::from_counts(counts).with_blanks(blanks as u8))
+    }
+
+    /// Collects the tiles used in some letters.
+    pub fn mask(&self, text: &str) -> Result<LetterMask, char> {
+        Ok(LetterMask(
+            self.split(text)?
+                .into_iter()
+                .fold(0, |mask, idx| mask | 1 << idx),
+        ))
+    }
+
+    /// Spells out each tile in some letters, in alphabet order, with each
+    /// blank as `?`.
+    pub fn spell(&self, letters: &SortedLetters) -> String {
+        let mut text = String::with_capacity(letters.len());
+        for (tile, &count) in self.tiles.iter().zip(letters.counts()) {
+            for _ in 0..count {
+                text.push_str(tile);
+            }
+        }
+        text.extend(std::iter::repeat_n(BLANK, letters.num_blanks()));
+        text
+    }
+
+    /// Spells out each tile in some letters on its own.  Blanks are left
+    /// out.
+    pub fn spell_tiles<'a>(&'a self, letters: &SortedLetters) -> Vec<&'a str> {
+        self.tiles
+            .iter()
+            .zip(letters.counts())
+            .flat_map(|(tile, &count)| std::iter::repeat_n(tile.as_str(), count as usize))
+            .collect()
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+/// Parses `english`, `spanish` or `welsh`, or a list of tiles separated by
+/// spaces or commas, such as `a b c ch d`.
+impl FromStr for Alphabet {
+    type Err = AlphabetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tiles = match s.trim().to_ascii_lowercase().as_str() {
+            "english" => return Ok(Self::english()),
+            "spanish" => SPANISH,
+            "welsh" => WELSH,
+            _ => s,
+        };
+        Self::new(
+            tiles
+                .split(|ch: char| ch.is_whitespace() || ch == ',')
+                .filter(|tile| !tile.is_empty()),
+        )
+    }
+}
+
+/// Writes the tiles separated by spaces, which `FromStr` reads back.
+impl Display for Alphabet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.tiles.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    fn spell(alphabet: &Alphabet, word: &str) -> Vec<String> {
+        let tiles = alphabet.split(word).unwrap();
+        tiles
+            .into_iter()
+            .map(|idx| alphabet.tiles[idx].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_split() {
+        let spanish = "spanish".parse::<Alphabet>().unwrap();
+        check!(spell(&spanish, "Chorro") == ["ch", "o", "rr", "o"]);
+        check!(spell(&spanish, "año") == ["a", "ñ", "o"]);
+        let welsh = "welsh".parse::<Alphabet>().unwrap();
+        check!(
+            spell(&welsh, "llongddrylliad")
+                == ["ll", "o", "ng", "dd", "r", "y", "ll", "i", "a", "d"]
+        );
+        check!(welsh.split("kayak") == Err('k'));
+        check!(
+            spell(&Alphabet::english(), "ice-cream") == ["i", "c", "e", "c", "r", "e", "a", "m"]
+        );
+    }
+
+    #[test]
+    fn test_letters() {
+        let spanish = "spanish".parse::<Alphabet>().unwrap();
+        let chorro = spanish.letters("chorro").unwrap();
+        check!(chorro.len() == 4);
+        check!(spanish.spell(&chorro) == "choorr");
+        check!(spanish.spell_tiles(&chorro) == ["ch", "o", "o", "rr"]);
+        check!(chorro.is_subset(&spanish.letters("rr o o ch").unwrap()));
+        check!(!chorro.is_subset(&spanish.letters("c h o r r o").unwrap()));
+        check!(spanish.letters("ch?").unwrap().num_blanks() == 1);
+
+        let english = Alphabet::english();
+        check!(english.letters("CAT?") == Ok(SortedLetters::from_word("cat?")));
+        check!(english.letters("ñu") == Err('ñ'));
+    }
+
+    #[test]
+    fn test_parse() {
+        let alphabet = "a, b, ch".parse::<Alphabet>().unwrap();
+        check!(alphabet.to_string() == "a b ch");
+        check!(!alphabet.is_english());
+        check!("english".parse::<Alphabet>().unwrap().is_english());
+        check!(Alphabet::english()
+            .to_string()
+            .parse::<Alphabet>()
+            .unwrap()
+            .is_english());
+        check!("".parse::<Alphabet>() == Err(AlphabetError::Empty));
+        check!("a b a".parse::<Alphabet>() == Err(AlphabetError::Duplicate("a".to_owned())));
+        check!("a 1".parse::<Alphabet>() == Err(AlphabetError::BadTile("1".to_owned())));
+        let too_many =
+            (0..33).map(|i| format!("{}{}", (b'a' + i / 26) as char, (b'a' + i % 26) as char));
+        check!(
+            Alphabet::new(too_many.collect::<Vec<_>>().iter().map(String::as_str))
+                == Err(AlphabetError::TooManyTiles(33))
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// How many letters are in each word of a phrase, as in the `(3,5)` after a
+/// crossword clue for ICE CREAM.  Words joined by a hyphen are written with
+/// `-` instead of `,`, as in `(3-3)` for POT-PIE.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Enumeration {
+    lengths: Vec<usize>,
+    /// What comes between each pair of words: `,` for a space or `-` for a
+    /// hyphen.
+    separators: Vec<char>,
+}
+
+impl Enumeration {
+    /// Works out the enumeration of a word or phrase from its spacing.
+    /// Hyphens join words, apostrophes don't split them, and anything else
+    /// that isn't a letter separates words.
+    pub fn of(text: &str) -> Self {
+        let mut enumeration = Self::default();
+        let mut len = 0;
+        let mut separator = None;
+        for ch in text.chars() {
+            if ch.is_alphabetic() {
+                if let Some(sep) = separator.take().filter(|_| len > 0) {
+                    enumeration.lengths.push(len);
+                    enumeration.separators.push(sep);
+                    len = 0;
+                }
+                len += 1;
+            } else if ch == '-' {
+                separator = Some('-');
+            } else if !matches!(ch, '\'' | '’') && separator.is_none() {
+                separator = Some(',');
+            }
+        }
+        if len > 0 || enumeration.lengths.is_empty() {
+            enumeration.lengths.push(len);
+        }
+        enumeration
+    }
+
+    /// Splits a word list line such as `ice cream (3,5)` into the phrase
+    /// and the enumeration after it, if there is one.
+    pub fn split_line(line: &str) -> (&str, Option<Self>) {
+        let Some(open) = line.strip_suffix(')').and_then(|rest| rest.rfind('(')) else {
+            return (line, None);
+        };
+        match line[open..].parse() {
+            Ok(enumeration) => (line[..open].trim_end(), Some(enumeration)),
+            Err(_) => (line, None),
+        }
+    }
+
+    /// The number of words.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// The total number of letters.
+    pub fn num_letters(&self) -> usize {
+        self.lengths.iter().sum()
+    }
+
+    pub fn lengths(&self) -> &[usize] {
+        &self.lengths
+    }
+
+    pub fn separators(&self) -> &[char] {
+        &self.separators
+    }
+
+    pub(crate) fn from_parts(lengths: Vec<usize>, separators: Vec<char>) -> Option<Self> {
+        let valid = !lengths.is_empty()
+            && separators.len() + 1 == lengths.len()
+            && separators.iter().all(|&sep| sep == ',' || sep == '-');
+        valid.then_some(Self {
+            lengths,
+            separators,
+        })
+    }
+
+    /// Spells out the letters of `text` with spaces and hyphens where this
+    /// enumeration puts them, so ICECREAM with `(3,5)` is ICE CREAM.
+    pub fn spell(&self, text: &str) -> String {
+        let mut letters = text.chars().filter(|ch| ch.is_alphabetic());
+        let mut spelled = String::with_capacity(text.len() + self.separators.len());
+        for (i, &len) in self.lengths.iter().enumerate() {
+            if i > 0 {
+                spelled.push(match self.separators[i - 1] {
+                    '-' => '-',
+                    _ => ' ',
+                });
+            }
+            spelled.extend(letters.by_ref().take(len));
+        }
+        spelled.extend(letters);
+        spelled
+    }
+}
+
+/// Parses enumerations such as `(4,5)`, `4,5`, `4 5` or `3-3`.
+impl FromStr for Enumeration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid enumeration: {s} (expected e.g. 4,5 or 3-3)");
+        let inner = s.trim();
+        let inner = inner
+            .strip_prefix('(')
+            .and_then(|inner| inner.strip_suffix(')'))
+            .unwrap_or(inner);
+        let mut lengths = Vec::new();
+        let mut separators = Vec::new();
+        let mut rest = inner.trim();
+        loop {
+            let end = rest
+                .find(|ch: char| !ch.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let len = rest[..end].parse::<usize>().map_err(|_| error())?;
+            if len == 0 {
+                return Err(error());
+            }
+            lengths.push(len);
+            rest = rest[end..].trim_start();
+            let Some(sep) = rest.chars().next() else {
+                break;
+            };
+            match sep {
+                ',' | '-' => {
+                    separators.push(sep);
+                    rest = rest[1..].trim_start();
+                }
+                _ if sep.is_ascii_digit() => separators.push(','),
+                _ => return Err(error()),
+            }
+        }
+        Ok(Self {
+            lengths,
+            separators,
+        })
+    }
+}
+
+impl Display for Enumeration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        for (i, len) in self.lengths.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", self.separators[i - 1])?;
+            }
+            write!(f, "{len}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    #[test]
+    fn test_of() {
+        check!(Enumeration::of("cat").to_string() == "(3)");
+        check!(Enumeration::of("ice cream").to_string() == "(3,5)");
+        check!(Enumeration::of("pot-pie").to_string() == "(3-3)");
+        check!(Enumeration::of("o'clock").to_string() == "(6)");
+        check!(Enumeration::of("jack-in-the-box").to_string() == "(4-2-3-3)");
+        check!(Enumeration::of("  up,  to date ").to_string() == "(2,2,4)");
+        check!(Enumeration::of("rock - paper").to_string() == "(4-5)");
+    }
+
+    #[test]
+    fn test_parse() {
+        check!("(4,5)".parse::<Enumeration>().unwrap() == Enumeration::of("ABCD EFGHI"));
+        check!("4 5".parse::<Enumeration>().unwrap().to_string() == "(4,5)");
+        check!("3-3".parse::<Enumeration>().unwrap().to_string() == "(3-3)");
+        check!("( 2, 3 )".parse::<Enumeration>().unwrap().to_string() == "(2,3)");
+        for bad in ["", "()", "4,", ",4", "4;5", "0", "x"] {
+            check!(bad.parse::<Enumeration>().is_err());
+        }
+    }
+
+    #[test]
+    fn test_split_line() {
+        let (phrase, enumeration) = Enumeration::split_line("ice cream (3,5)");
+        check!(phrase == "ice cream");
+        check!(enumeration == Some(Enumeration::of("ice cream")));
+        check!(Enumeration::split_line("icecream") == ("icecream", None));
+        check!(Enumeration::split_line("jaws (film)") == ("jaws (film)", None));
+    }
+
+    #[test]
+    fn test_spell() {
+        let enumeration = "(3,5)".parse::<Enumeration>().unwrap();
+        check!(enumeration.spell("ICECREAM") == "ICE CREAM");
+        check!("3-3".parse::<Enumeration>().unwrap().spell("potpie") == "pot-pie");
+    }
+}
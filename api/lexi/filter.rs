@@ -1,7 +1,15 @@
 use super::Entry;
+use super::Enumeration;
 use super::LengthRange;
+use super::LetterCount;
 use super::LetterMask;
+use super::Lexicon;
+use super::Pattern;
+use super::Popularity;
+use super::Shape;
 use super::SortedLetters;
+use regex::Regex;
+use std::borrow::Cow;
 
 #[derive(Debug, Clone)]
 pub enum Check {
@@ -11,6 +19,40 @@ pub enum Check {
     IncludeLetters(LetterMask),
     Contains(SortedLetters),
     Contained(SortedLetters),
+    Pattern(Pattern),
+    Prefix(String),
+    Suffix(String),
+    Substring(String),
+    LetterCount(LetterCount),
+    /// How many letters are in each word of a phrase, such as `(3,5)`.
+    Enumeration(Enumeration),
+    /// The number of vowels, with or without Y.
+    Vowels {
+        range: LengthRange,
+        y_is_vowel: bool,
+    },
+    /// The number of consonants, with or without Y.
+    Consonants {
+        range: LengthRange,
+        y_is_vowel: bool,
+    },
+    /// Where the vowels and consonants are, such as `CVCCVC`.
+    Shape {
+        shape: Shape,
+        y_is_vowel: bool,
+    },
+    /// The word as written in the word list matches this regex.
+    Regex(Regex),
+    /// Rated at least this popular.
+    MinPopularity(Popularity),
+    /// Ranked this high or higher in the popularity list, counting from 0.
+    MaxRank(usize),
+    /// Matches if any of the checks match.
+    Any(Vec<Check>),
+    /// Matches if all of the checks match.
+    All(Vec<Check>),
+    /// Matches if the check doesn't.
+    Not(Box<Check>),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -27,43 +69,103 @@ impl Filter {
         self.checks.is_empty()
     }
 
+    pub(super) fn checks(&self) -> &[Check] {
+        &self.checks
+    }
+
+    /// The number of letters that every match must have, if the filter
+    /// requires one.
+    pub fn exact_length(&self) -> Option<usize> {
+        self.checks.iter().find_map(Check::exact_length)
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.checks.iter().all(|check| check.matches(entry))
+    }
+
+    /// Normalizes the text in prefix, suffix and substring checks the same
+    /// way as a lexicon's words, so that "café" finds "cafe" when accents
+    /// are folded, and "ice cr" finds "ice cream".
+    pub fn normalized(&self, lexicon: &Lexicon) -> Cow<'_, Filter> {
+        if self.checks.iter().all(|check| check.is_normalized(lexicon)) {
+            return Cow::Borrowed(self);
+        }
+        let mut filter = self.clone();
+        for check in &mut filter.checks {
+            check.normalize(lexicon);
+        }
+        Cow::Owned(filter)
+    }
+}
+
+impl Check {
     pub fn matches(&self, entry: &Entry) -> bool {
-        for check in &self.checks {
-            match check {
-                Check::SingleWord(want_single) => {
-                    if entry.is_single_word() != *want_single {
-                        return false;
-                    }
-                }
-
-                Check::Length(range) => {
-                    if !range.contains(entry.num_letters()) {
-                        return false;
-                    }
-                }
-                Check::ExcludeLetters(mask) => {
-                    if mask.0 & entry.mask.0 != 0 {
-                        return false;
-                    }
-                }
-                Check::IncludeLetters(mask) => {
-                    if mask.0 & entry.mask.0 != mask.0 {
-                        return false;
-                    }
-                }
-                Check::Contains(sorted) => {
-                    if !entry.sorted.is_superset(sorted) {
-                        return false;
-                    }
-                }
-                Check::Contained(sorted) => {
-                    if !entry.sorted.is_subset(sorted) {
-                        return false;
-                    }
-                }
+        match self {
+            Check::SingleWord(want_single) => entry.is_single_word() == *want_single,
+            Check::Length(range) => range.contains(entry.num_letters()),
+            Check::ExcludeLetters(mask) => mask.0 & entry.mask.0 == 0,
+            Check::IncludeLetters(mask) => mask.0 & entry.mask.0 == mask.0,
+            Check::Contains(sorted) => entry.sorted.is_superset(sorted),
+            Check::Contained(sorted) => entry.in_alphabet && entry.sorted.is_subset(sorted),
+            Check::Pattern(pattern) => entry.len == pattern.len() && pattern.matches(&entry.text),
+            Check::Prefix(prefix) => entry.text.starts_with(prefix.as_str()),
+            Check::Suffix(suffix) => entry.text.ends_with(suffix.as_str()),
+            Check::Substring(needle) => entry.text.contains(needle.as_str()),
+            Check::LetterCount(count) => count.matches(&entry.text),
+            Check::Enumeration(enumeration) => entry.enumeration == *enumeration,
+            Check::Vowels { range, y_is_vowel } => range.contains(entry.vowels.count(*y_is_vowel)),
+            Check::Consonants { range, y_is_vowel } => {
+                range.contains(entry.num_consonants(*y_is_vowel))
+            }
+            Check::Shape { shape, y_is_vowel } => {
+                entry.len == shape.len() && shape.matches(&entry.vowels, *y_is_vowel)
+            }
+            Check::Regex(re) => re.is_match(entry.word()),
+            Check::MinPopularity(popularity) => entry.popularity >= *popularity,
+            Check::MaxRank(max) => entry.rank.is_some_and(|rank| rank <= *max),
+            Check::Any(checks) => checks.iter().any(|check| check.matches(entry)),
+            Check::All(checks) => checks.iter().all(|check| check.matches(entry)),
+            Check::Not(check) => !check.matches(entry),
+        }
+    }
+
+    fn is_normalized(&self, lexicon: &Lexicon) -> bool {
+        match self {
+            Check::Prefix(text) | Check::Suffix(text) | Check::Substring(text) => {
+                lexicon.search_text(text) == text.as_str()
+            }
+            Check::Any(checks) | Check::All(checks) => {
+                checks.iter().all(|check| check.is_normalized(lexicon))
+            }
+            Check::Not(check) => check.is_normalized(lexicon),
+            _ => true,
+        }
+    }
+
+    fn normalize(&mut self, lexicon: &Lexicon) {
+        match self {
+            Check::Prefix(text) | Check::Suffix(text) | Check::Substring(text) => {
+                *text = lexicon.search_text(text).into_owned();
             }
+            Check::Any(checks) | Check::All(checks) => {
+                checks.iter_mut().for_each(|check| check.normalize(lexicon));
+            }
+            Check::Not(check) => check.normalize(lexicon),
+            _ => {}
+        }
+    }
+
+    /// The number of letters that every match must have, if the check
+    /// requires one.
+    fn exact_length(&self) -> Option<usize> {
+        match self {
+            Check::Length(range) => range.exact(),
+            Check::Pattern(pattern) => Some(pattern.len()),
+            Check::Shape { shape, .. } => Some(shape.len()),
+            Check::Enumeration(enumeration) => Some(enumeration.num_letters()),
+            Check::All(checks) => checks.iter().find_map(Check::exact_length),
+            _ => None,
         }
-        true
     }
 }
 
@@ -119,15 +221,143 @@ impl FilterBuilder {
         self
     }
 
+    pub fn pattern(mut self, pattern: Option<Pattern>) -> Self {
+        if let Some(pattern) = pattern {
+            self.checks.push(Check::Pattern(pattern));
+        }
+        self
+    }
+
+    pub fn prefix(mut self, prefix: Option<&str>) -> Self {
+        if let Some(prefix) = prefix.filter(|s| !s.is_empty()) {
+            self.checks.push(Check::Prefix(prefix.to_lowercase()));
+        }
+        self
+    }
+
+    pub fn suffix(mut self, suffix: Option<&str>) -> Self {
+        if let Some(suffix) = suffix.filter(|s| !s.is_empty()) {
+            self.checks.push(Check::Suffix(suffix.to_lowercase()));
+        }
+        self
+    }
+
+    pub fn substring(mut self, needle: Option<&str>) -> Self {
+        if let Some(needle) = needle.filter(|s| !s.is_empty()) {
+            self.checks.push(Check::Substring(needle.to_lowercase()));
+        }
+        self
+    }
+
+    pub fn letter_counts(mut self, counts: impl IntoIterator<Item = LetterCount>) -> Self {
+        self.checks
+            .extend(counts.into_iter().map(Check::LetterCount));
+        self
+    }
+
+    pub fn enumeration(mut self, enumeration: Option<Enumeration>) -> Self {
+        if let Some(enumeration) = enumeration {
+            self.checks.push(Check::Enumeration(enumeration));
+        }
+        self
+    }
+
+    pub fn vowels(mut self, range: impl TryInto<LengthRange>, y_is_vowel: bool) -> Self {
+        if let Ok(range) = range.try_into() {
+            self.checks.push(Check::Vowels { range, y_is_vowel });
+        }
+        self
+    }
+
+    pub fn consonants(mut self, range: impl TryInto<LengthRange>, y_is_vowel: bool) -> Self {
+        if let Ok(range) = range.try_into() {
+            self.checks.push(Check::Consonants { range, y_is_vowel });
+        }
+        self
+    }
+
+    pub fn shape(mut self, shape: Option<Shape>, y_is_vowel: bool) -> Self {
+        if let Some(shape) = shape {
+            self.checks.push(Check::Shape { shape, y_is_vowel });
+        }
+        self
+    }
+
+    pub fn regex(mut self, re: Option<Regex>) -> Self {
+        if let Some(re) = re {
+            self.checks.push(Check::Regex(re));
+        }
+        self
+    }
+
+    pub fn min_popularity(mut self, popularity: Option<Popularity>) -> Self {
+        if let Some(popularity) = popularity {
+            self.checks.push(Check::MinPopularity(popularity));
+        }
+        self
+    }
+
+    pub fn max_rank(mut self, max: Option<usize>) -> Self {
+        if let Some(max) = max {
+            self.checks.push(Check::MaxRank(max));
+        }
+        self
+    }
+
+    /// Adds a check that matches if any of the filters would.
+    #[allow(dead_code)]
+    pub fn any(mut self, builders: impl IntoIterator<Item = FilterBuilder>) -> Self {
+        let checks = builders
+            .into_iter()
+            .map(FilterBuilder::into_check)
+            .collect();
+        self.checks.push(Check::Any(checks));
+        self
+    }
+
+    /// Adds a check that matches if all of the filters would.
+    #[allow(dead_code)]
+    pub fn all(mut self, builders: impl IntoIterator<Item = FilterBuilder>) -> Self {
+        let checks = builders
+            .into_iter()
+            .map(FilterBuilder::into_check)
+            .collect();
+        self.checks.push(Check::All(checks));
+        self
+    }
+
+    /// Adds a check that matches if the filter wouldn't.
+    #[allow(dead_code)]
+    pub fn not(mut self, builder: FilterBuilder) -> Self {
+        self.checks.push(Check::Not(Box::new(builder.into_check())));
+        self
+    }
+
+    /// Adds a check, such as one parsed from a query.
+    pub fn check(mut self, check: Option<Check>) -> Self {
+        if let Some(check) = check {
+            self.checks.push(check);
+        }
+        self
+    }
+
+    fn into_check(self) -> Check {
+        match <[Check; 1]>::try_from(self.checks) {
+            Ok([check]) => check,
+            Err(checks) => Check::All(checks),
+        }
+    }
+
     pub fn build(self) -> Filter {
         Filter::new(self.checks)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_conversion)]
 mod tests {
     use super::*;
-    use crate::lexi::Lexicon;
+    use crate::lexi::{Accents, Alphabet};
 
     #[test]
     fn test_include_letters() {
@@ -185,4 +415,216 @@ mod tests {
             ["calmness"]
         );
     }
+
+    #[test]
+    fn test_combinators() {
+        let filter = FilterBuilder::new()
+            .length("5")
+            .any([
+                FilterBuilder::new().contains("q"),
+                FilterBuilder::new().contains("z"),
+            ])
+            .not(FilterBuilder::new().pattern("????s".parse().ok()))
+            .build();
+        let lex = Lexicon::from_iter(["quilt", "quits", "zebra", "zones", "plaza", "hello"]);
+
+        assert_eq!(filter.exact_length(), Some(5));
+        assert_eq!(
+            lex.matching_words(&filter).collect::<Vec<_>>(),
+            ["quilt", "zebra", "plaza"]
+        );
+    }
+
+    #[test]
+    fn test_affixes() {
+        let filter = FilterBuilder::new()
+            .prefix(Some("UN"))
+            .suffix(Some("ing"))
+            .build();
+        let lex = Lexicon::from_iter(["undoing", "unquiet", "doing", "unsung", "untying"]);
+
+        assert_eq!(
+            lex.matching_words(&filter).collect::<Vec<_>>(),
+            ["undoing", "untying"]
+        );
+    }
+
+    #[test]
+    fn test_letter_counts() {
+        let filter = FilterBuilder::new()
+            .letter_counts(LetterCount::parse_list("e=2+,s=1").unwrap())
+            .build();
+        let lex = Lexicon::from_iter(["seems", "steep", "sleeves", "stereo", "eel"]);
+
+        assert_eq!(
+            lex.matching_words(&filter).collect::<Vec<_>>(),
+            ["steep", "stereo"]
+        );
+    }
+
+    #[test]
+    fn test_pattern() {
+        let filter = FilterBuilder::new().pattern(".A.E".parse().ok()).build();
+        let lex = Lexicon::from_iter(["gaze", "game", "gazed", "ga-ze", "face", "axe", "gene"]);
+
+        assert_eq!(filter.exact_length(), Some(4));
+        assert_eq!(
+            lex.matching_words(&filter).collect::<Vec<_>>(),
+            ["gaze", "game", "ga-ze", "face"]
+        );
+    }
+
+    #[test]
+    fn test_vowels_and_consonants() {
+        let lex = Lexicon::from_iter(["rhythm", "strength", "audio", "syzygy", "banana"]);
+        let words = |filter: Filter| {
+            lex.matching_words(&filter)
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            words(FilterBuilder::new().vowels("0", false).build()),
+            ["rhythm", "syzygy"]
+        );
+        assert_eq!(
+            words(FilterBuilder::new().vowels("0", true).build()),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            words(FilterBuilder::new().consonants("5-6", true).build()),
+            ["rhythm"]
+        );
+        assert_eq!(
+            words(FilterBuilder::new().consonants("1", false).build()),
+            ["audio"]
+        );
+    }
+
+    #[test]
+    fn test_shape() {
+        let lex = Lexicon::from_iter(["banana", "bandit", "rhymed", "canter", "cantor", "candy"]);
+        let filter = FilterBuilder::new()
+            .shape("CVCCVC".parse().ok(), false)
+            .build();
+        assert_eq!(filter.exact_length(), Some(6));
+        assert_eq!(
+            lex.matching_words(&filter).collect::<Vec<_>>(),
+            ["bandit", "canter", "cantor"]
+        );
+
+        let filter = FilterBuilder::new()
+            .shape("CCVCVC".parse().ok(), true)
+            .build();
+        assert_eq!(lex.matching_words(&filter).collect::<Vec<_>>(), ["rhymed"]);
+    }
+
+    #[test]
+    fn test_popularity() {
+        let lex = Lexicon::from_iter(["pear", "fig", "apple", "kiwi", "banana"])
+            .with_popular_words(["apple", "fig", "pear"], 2);
+        let words = |filter: Filter| {
+            lex.matching_words(&filter)
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            words(
+                FilterBuilder::new()
+                    .min_popularity(Some(Popularity::High))
+                    .build()
+            ),
+            ["fig", "apple"]
+        );
+        assert_eq!(
+            words(
+                FilterBuilder::new()
+                    .min_popularity(Some(Popularity::Medium))
+                    .build()
+            ),
+            ["pear", "fig", "apple"]
+        );
+        assert_eq!(
+            words(FilterBuilder::new().max_rank(Some(0)).build()),
+            ["apple"]
+        );
+    }
+
+    #[test]
+    fn test_accents() {
+        let words = ["café", "cafe", "crème", "naïve"];
+        let folded = Lexicon::new(words, Alphabet::default(), Accents::Fold);
+        let distinct = Lexicon::new(words, Alphabet::default(), Accents::Distinct);
+        let search = |lex: &Lexicon, filter: Filter| {
+            lex.matching_words(&filter)
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        let filter = || FilterBuilder::new().suffix(Some("fé")).build();
+        assert_eq!(search(&folded, filter()), ["café", "cafe"]);
+        assert_eq!(search(&distinct, filter()), ["café"]);
+
+        let filter = || FilterBuilder::new().pattern("CR?ME".parse().ok()).build();
+        assert_eq!(search(&folded, filter()), ["crème"]);
+        assert_eq!(search(&distinct, filter()), ["crème"]);
+
+        let filter = || FilterBuilder::new().contained("aefc").build();
+        assert_eq!(search(&folded, filter()), ["café", "cafe"]);
+        assert_eq!(search(&distinct, filter()), ["cafe"]);
+    }
+
+    #[test]
+    fn test_phrases() {
+        let lex = Lexicon::from_iter([
+            "ice cream",
+            "icecap (3,3)",
+            "ice-cold",
+            "icecream",
+            "cream tea",
+        ]);
+        let search = |filter: FilterBuilder| {
+            lex.matching_words(&filter.build())
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        let enumeration = |s: &str| FilterBuilder::new().enumeration(s.parse().ok());
+        assert_eq!(search(enumeration("(3,5)")), ["ice cream"]);
+        assert_eq!(search(enumeration("3,3")), ["icecap"]);
+        assert_eq!(search(enumeration("3-4")), ["ice-cold"]);
+        assert_eq!(search(enumeration("8")), ["icecream"]);
+        assert_eq!(
+            search(FilterBuilder::new().prefix(Some("ice c"))),
+            ["ice cream", "icecap", "ice-cold", "icecream"]
+        );
+        assert_eq!(
+            search(FilterBuilder::new().substring(Some("m t"))),
+            ["cream tea"]
+        );
+        assert_eq!(
+            search(FilterBuilder::new().pattern("ICE CREAM".parse().ok())),
+            ["ice cream", "icecream"]
+        );
+        assert_eq!(
+            search(
+                FilterBuilder::new()
+                    .single_word(Some(true))
+                    .contained("aceeimrc")
+            ),
+            ["icecream"]
+        );
+    }
+
+    #[test]
+    fn test_contained_with_blanks() {
+        let filter = Filter::new(vec![Check::Contained(SortedLetters::from_word("qis?"))]);
+        let lex = Lexicon::from_iter(["qi", "quiz", "quits", "qis", "squid", "suq"]);
+
+        assert_eq!(
+            lex.matching_words(&filter).collect::<Vec<_>>(),
+            ["qi", "qis", "suq"]
+        );
+    }
 }
@@ -0,0 +1,394 @@
+//! A precompiled binary form of a lexicon, which loads much faster than a
+//! word list because nothing has to be recomputed.
+//!
+//! All numbers are little-endian.  The file is laid out as:
+//!
+//! ```text
+//! header:  magic (8 bytes), version (u32), popular_threshold (u32),
+//!          anagram_threshold (u32), accents (u32, 0 to fold or 1 for
+//!          distinct), size of alphabet (u32), number of entries (u32),
+//!          size of word data (u32), number of anagram buckets (u32)
+//! alphabet: the tiles, separated by spaces, as UTF-8
+//! words:   every word, concatenated, as UTF-8
+//! entries: word offset (u32), word length (u32), rank (u32, or
+//!          u32::MAX if none), frequency (u64, or u64::MAX if none),
+//!          mask (u32), letters (u32), one_word (u8), in_alphabet (u8),
+//!          tile counts (32 × u8), vowel positions (u64), Y positions
+//!          (u64), number of vowels (u16), number of Ys (u16), number of
+//!          words in the enumeration (u16), the length of each word
+//!          (u16), then the separator between each pair of words (u8,
+//!          `,` or `-`)
+//! buckets: tile counts (32 × u8), number of entries (u32), then the
+//!          index of each entry (u32)
+//! ```
+
+use super::alphabet::MAX_TILES;
+use super::{
+    index_by_length, letters_only, Accents, Alphabet, Entry, Enumeration, LetterMask, Lexicon,
+    Popularity, SortedLetters, Vowels,
+};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+const MAGIC: &[u8; 8] = b"WFINDEX\0";
+
+/// Bump this whenever the layout changes.  Files with any other version are
+/// rejected, and have to be rebuilt with `wf index build`.
+pub const VERSION: u32 = 6;
+
+/// The fewest bytes an entry can take up, with an empty enumeration.
+const MIN_ENTRY_SIZE: usize = 4 + 4 + 4 + 8 + 4 + 4 + 1 + 1 + MAX_TILES + 8 + 8 + 2 + 2 + 2;
+
+/// The fewest bytes a bucket can take up, with no entries.
+const MIN_BUCKET_SIZE: usize = MAX_TILES + 4;
+
+const NO_RANK: u32 = u32::MAX;
+const NO_FREQUENCY: u64 = u64::MAX;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IndexError {
+    #[error("not a lexicon index")]
+    BadMagic,
+    #[error("index is version {found}, but this program needs version {VERSION}; rebuild it with `wf index build`")]
+    WrongVersion { found: u32 },
+    #[error("index is truncated")]
+    Truncated,
+    #[error("index is corrupt: {0}")]
+    Corrupt(&'static str),
+}
+
+impl Lexicon<'_> {
+    /// Writes the lexicon in index format.
+    pub fn write_index(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut buckets = self.from_sorted.iter().collect::<Vec<_>>();
+        buckets.sort_by_key(|(_, indexes)| indexes.first().copied());
+
+        let alphabet = self.alphabet.to_string();
+        let words_size = self.entries.iter().map(|e| e.word.len()).sum::<usize>();
+        out.write_all(MAGIC)?;
+        write_u32(out, VERSION)?;
+        write_u32(out, to_u32(self.popular_threshold)?)?;
+        write_u32(out, to_u32(self.anagram_threshold)?)?;
+        write_u32(out, self.accents.to_u32())?;
+        write_u32(out, to_u32(alphabet.len())?)?;
+        write_u32(out, to_u32(self.entries.len())?)?;
+        write_u32(out, to_u32(words_size)?)?;
+        write_u32(out, to_u32(buckets.len())?)?;
+
+        out.write_all(alphabet.as_bytes())?;
+
+        for entry in &self.entries {
+            out.write_all(entry.word.as_bytes())?;
+        }
+
+        let mut offset = 0;
+        for entry in &self.entries {
+            write_u32(out, to_u32(offset)?)?;
+            write_u32(out, to_u32(entry.word.len())?)?;
+            let rank = match entry.rank {
+                Some(rank) => to_u32(rank)?,
+                None => NO_RANK,
+            };
+            write_u32(out, rank)?;
+            out.write_all(&entry.frequency.unwrap_or(NO_FREQUENCY).to_le_bytes())?;
+            write_u32(out, entry.mask.0)?;
+            write_u32(out, to_u32(entry.len)?)?;
+            out.write_all(&[entry.one_word as u8, entry.in_alphabet as u8])?;
+            out.write_all(entry.sorted.counts())?;
+            let (positions, y_positions, count, ys) = entry.vowels.to_parts();
+            out.write_all(&positions.to_le_bytes())?;
+            out.write_all(&y_positions.to_le_bytes())?;
+            out.write_all(&count.to_le_bytes())?;
+            out.write_all(&ys.to_le_bytes())?;
+            let enumeration = &entry.enumeration;
+            write_u16(out, to_u16(enumeration.len())?)?;
+            for &len in enumeration.lengths() {
+                write_u16(out, to_u16(len)?)?;
+            }
+            for &sep in enumeration.separators() {
+                out.write_all(&[sep as u8])?;
+            }
+            offset += entry.word.len();
+        }
+
+        for (sorted, indexes) in buckets {
+            out.write_all(sorted.counts())?;
+            write_u32(out, to_u32(indexes.len())?)?;
+            for &idx in indexes {
+                write_u32(out, to_u32(idx)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a lexicon from index data.  The entries borrow their words
+    /// from `bytes`.
+    pub fn from_index(bytes: &[u8]) -> Result<Lexicon<'_>, IndexError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(IndexError::BadMagic);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(IndexError::WrongVersion { found: version });
+        }
+        let popular_threshold = reader.u32()? as usize;
+        let anagram_threshold = reader.u32()? as usize;
+        let accents = Accents::from_u32(reader.u32()?)
+            .ok_or(IndexError::Corrupt("unknown accents setting"))?;
+        let alphabet_size = reader.u32()? as usize;
+        let num_entries = reader.u32()? as usize;
+        let words_size = reader.u32()? as usize;
+        let num_buckets = reader.u32()? as usize;
+
+        let alphabet = std::str::from_utf8(reader.take(alphabet_size)?)
+            .ok()
+            .and_then(|tiles| tiles.parse::<Alphabet>().ok())
+            .ok_or(IndexError::Corrupt("bad alphabet"))?;
+        let words = std::str::from_utf8(reader.take(words_size)?)
+            .map_err(|_| IndexError::Corrupt("words are not UTF-8"))?;
+
+        let mut entries = Vec::with_capacity(reader.count(num_entries, MIN_ENTRY_SIZE)?);
+        for _ in 0..num_entries {
+            let offset = reader.u32()? as usize;
+            let word_len = reader.u32()? as usize;
+            let word = words
+                .get(offset..offset + word_len)
+                .ok_or(IndexError::Corrupt("word out of range"))?;
+            let rank = match reader.u32()? {
+                NO_RANK => None,
+                rank => Some(rank as usize),
+            };
+            let frequency = match reader.u64()? {
+                NO_FREQUENCY => None,
+                frequency => Some(frequency),
+            };
+            let mask = LetterMask(reader.u32()?);
+            let len = reader.u32()? as usize;
+            let one_word = reader.u8()? != 0;
+            let in_alphabet = reader.u8()? != 0;
+            let sorted = reader.sorted()?;
+            let vowels =
+                Vowels::from_parts((reader.u64()?, reader.u64()?, reader.u16()?, reader.u16()?));
+            let num_words = reader.u16()? as usize;
+            let lengths = (0..num_words)
+                .map(|_| Ok(reader.u16()? as usize))
+                .collect::<Result<Vec<_>, IndexError>>()?;
+            let separators = (1..num_words)
+                .map(|_| Ok(reader.u8()? as char))
+                .collect::<Result<Vec<_>, IndexError>>()?;
+            let enumeration = Enumeration::from_parts(lengths, separators)
+                .ok_or(IndexError::Corrupt("bad enumeration"))?;
+            entries.push(Entry {
+                word,
+                text: letters_only(alphabet.normalize(word, accents)),
+                in_alphabet,
+                rank,
+                frequency,
+                popularity: Popularity::Low,
+                mask,
+                sorted,
+                len,
+                one_word,
+                enumeration,
+                vowels,
+            });
+        }
+
+        let mut from_sorted = HashMap::with_capacity(reader.count(num_buckets, MIN_BUCKET_SIZE)?);
+        for _ in 0..num_buckets {
+            let sorted = reader.sorted()?;
+            let count = reader.u32()? as usize;
+            let indexes = (0..count)
+                .map(|_| match reader.u32()? as usize {
+                    idx if idx < num_entries => Ok(idx),
+                    _ => Err(IndexError::Corrupt("entry out of range")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            from_sorted.insert(sorted, indexes);
+        }
+        if reader.pos != bytes.len() {
+            return Err(IndexError::Corrupt("trailing data"));
+        }
+
+        let mut lexicon = Lexicon {
+            by_length: index_by_length(&entries),
+            affixes: OnceLock::new(),
+            tile_words: OnceLock::new(),
+            entries,
+            from_sorted,
+            popular_threshold,
+            anagram_threshold,
+            alphabet,
+            accents,
+        };
+        lexicon.rate_entries();
+        Ok(lexicon)
+    }
+}
+
+fn to_u32(n: usize) -> io::Result<u32> {
+    u32::try_from(n).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "lexicon too large"))
+}
+
+fn to_u16(n: usize) -> io::Result<u16> {
+    u16::try_from(n).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "phrase too long"))
+}
+
+fn write_u16(out: &mut impl Write, n: u16) -> io::Result<()> {
+    out.write_all(&n.to_le_bytes())
+}
+
+fn write_u32(out: &mut impl Write, n: u32) -> io::Result<()> {
+    out.write_all(&n.to_le_bytes())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], IndexError> {
+        let end = self.pos.checked_add(n).ok_or(IndexError::Truncated)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or(IndexError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Checks that `n` records of at least `size` bytes each could fit in
+    /// what's left, before anything is allocated for them.
+    fn count(&self, n: usize, size: usize) -> Result<usize, IndexError> {
+        match n.checked_mul(size) {
+            Some(total) if total <= self.bytes.len() - self.pos => Ok(n),
+            _ => Err(IndexError::Corrupt("count too large")),
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8, IndexError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, IndexError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, IndexError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, IndexError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn sorted(&mut self) -> Result<SortedLetters, IndexError> {
+        let counts = self.take(MAX_TILES)?;
+        Ok(SortedLetters::from_counts(counts.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{check, let_assert};
+
+    fn build() -> Vec<u8> {
+        let lex = Lexicon::from_iter([
+            "stone",
+            "notes",
+            "onset",
+            "tones",
+            "pot-pie",
+            "cat",
+            "icecream (3,5)",
+        ])
+        .with_popular_words(["cat", "notes"], 1);
+        let mut bytes = Vec::new();
+        lex.write_index(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = build();
+        let lex = Lexicon::from_index(&bytes).unwrap();
+        check!(lex.len() == 7);
+        check!(lex.popular_threshold == 1);
+
+        let entries = lex.entries().collect::<Vec<_>>();
+        check!(entries[4].word() == "pot-pie");
+        check!(!entries[4].is_single_word());
+        check!(entries[4].len() == 6);
+        check!(*entries[4].vowels() == Vowels::new("pot-pie"));
+        check!(entries[4].enumeration().to_string() == "(3-3)");
+        check!(entries[6].word() == "icecream");
+        check!(entries[6].phrase() == "ice cream");
+        check!(!entries[6].is_single_word());
+        check!(entries[5].rank() == Some(0));
+        check!(lex.rate(entries[5]) == Popularity::High);
+        check!(lex.rate(entries[1]) == Popularity::Medium);
+        check!(entries[5].frequency() == Some(2));
+        check!(entries[0].rank() == None);
+
+        let anagrams = lex
+            .solve_anagram(&SortedLetters::from_word("tones"))
+            .map(|e| e.word())
+            .collect::<Vec<_>>();
+        check!(anagrams == ["stone", "notes", "onset", "tones"]);
+    }
+
+    #[test]
+    fn test_accents() {
+        let lex = Lexicon::new(["café", "cafe"], Alphabet::default(), Accents::Distinct);
+        let mut bytes = Vec::new();
+        lex.write_index(&mut bytes).unwrap();
+        let lex = Lexicon::from_index(&bytes).unwrap();
+        check!(lex.accents() == Accents::Distinct);
+        let entries = lex.entries().collect::<Vec<_>>();
+        check!(entries[0].text() == "café");
+        check!(!entries[0].is_in_alphabet());
+        check!(entries[1].is_in_alphabet());
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let spanish = "spanish".parse::<Alphabet>().unwrap();
+        let lex = Lexicon::new(["chorro", "roncha"], spanish.clone(), Accents::Fold);
+        let mut bytes = Vec::new();
+        lex.write_index(&mut bytes).unwrap();
+        let lex = Lexicon::from_index(&bytes).unwrap();
+        check!(*lex.alphabet() == spanish);
+        let rack = lex.parse_letters("o rr o ch").unwrap();
+        let anagrams = lex
+            .solve_anagram(&rack)
+            .map(|e| e.word())
+            .collect::<Vec<_>>();
+        check!(anagrams == ["chorro"]);
+    }
+
+    #[test]
+    fn test_wrong_version() {
+        let mut bytes = build();
+        bytes[8..12].copy_from_slice(&(VERSION - 1).to_le_bytes());
+        let_assert!(Err(e) = Lexicon::from_index(&bytes));
+        check!(e == IndexError::WrongVersion { found: VERSION - 1 });
+    }
+
+    #[test]
+    fn test_bad_data() {
+        let bytes = build();
+        check!(Lexicon::from_index(b"not an index").err() == Some(IndexError::BadMagic));
+        let truncated = &bytes[..bytes.len() - 1];
+        check!(Lexicon::from_index(truncated).err() == Some(IndexError::Truncated));
+        for header in [28..32, 36..40] {
+            let mut huge = bytes.clone();
+            huge[header].copy_from_slice(&u32::MAX.to_le_bytes());
+            let_assert!(Err(IndexError::Corrupt(_)) = Lexicon::from_index(&huge));
+        }
+    }
+}
@@ -15,6 +15,11 @@ impl LengthRange {
     pub fn contains(&self, n: usize) -> bool {
         (self.min..=self.max).contains(&n)
     }
+
+    /// The only length in the range, if there is just one.
+    pub fn exact(&self) -> Option<usize> {
+        (self.min == self.max).then_some(self.min)
+    }
 }
 
 impl FromStr for LengthRange {
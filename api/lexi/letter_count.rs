@@ -0,0 +1,105 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// How many times a letter may appear in a word, such as `e=2+` for at
+/// least two Es, `s=1` for exactly one S or `a=1-2` for one or two As.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LetterCount {
+    letter: char,
+    min: usize,
+    max: usize,
+}
+
+impl LetterCount {
+    pub fn new(letter: char, min: usize, max: usize) -> Self {
+        Self {
+            letter: letter.to_ascii_lowercase(),
+            min,
+            max,
+        }
+    }
+
+    /// Counts the letter in a word's text, so a letter that is part of a
+    /// digraph tile such as Spanish CH still counts.
+    pub fn matches(&self, text: &str) -> bool {
+        let count = text
+            .chars()
+            .filter(|ch| ch.to_ascii_lowercase() == self.letter)
+            .count();
+        (self.min..=self.max).contains(&count)
+    }
+
+    /// Parses a comma-separated list of counts, such as `e=2+,s=1`.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl FromStr for LetterCount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn try_parse(s: &str) -> Option<LetterCount> {
+            let (letter, count) = s.split_once('=')?;
+            let mut letters = letter.trim().chars();
+            let letter = letters.next().filter(char::is_ascii_alphabetic)?;
+            if letters.next().is_some() {
+                return None;
+            }
+            let count = count.trim();
+            let (min, max) = if let Some(min) = count.strip_suffix('+') {
+                (min.parse().ok()?, usize::MAX)
+            } else if let Some((min, max)) = count.split_once('-') {
+                (min.parse().ok()?, max.parse().ok()?)
+            } else {
+                let n = count.parse().ok()?;
+                (n, n)
+            };
+            (min <= max).then(|| LetterCount::new(letter, min, max))
+        }
+        try_parse(s)
+            .ok_or_else(|| format!("Invalid letter count: {s} (expected e.g. e=2, e=2+ or e=1-3)"))
+    }
+}
+
+impl Display for LetterCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (min, usize::MAX) => write!(f, "{}={min}+", self.letter),
+            (min, max) if min == max => write!(f, "{}={min}", self.letter),
+            (min, max) => write!(f, "{}={min}-{max}", self.letter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    #[test]
+    fn test_parse() {
+        check!("e=2+".parse() == Ok(LetterCount::new('e', 2, usize::MAX)));
+        check!("S=1".parse() == Ok(LetterCount::new('s', 1, 1)));
+        check!("a=1-3".parse() == Ok(LetterCount::new('a', 1, 3)));
+        for bad in ["e", "e=", "ee=1", "1=1", "e=x", "e=3-1"] {
+            check!(bad.parse::<LetterCount>().is_err());
+        }
+        let list = LetterCount::parse_list("e=2+, s=1").unwrap();
+        check!(list.iter().map(|c| c.to_string()).collect::<Vec<_>>() == ["e=2+", "s=1"]);
+    }
+
+    #[test]
+    fn test_matches() {
+        let text = "Sleepless";
+        check!("e=3".parse::<LetterCount>().unwrap().matches(text));
+        check!("e=2+".parse::<LetterCount>().unwrap().matches(text));
+        check!(!"s=1".parse::<LetterCount>().unwrap().matches(text));
+        check!("s=3".parse::<LetterCount>().unwrap().matches(text));
+        check!("z=0".parse::<LetterCount>().unwrap().matches(text));
+    }
+}
@@ -0,0 +1,431 @@
+use super::IndexError;
+use super::Lexicon;
+use super::Lexicons;
+use super::DEFAULT_POPULAR_THRESHOLD;
+use super::{Accents, Alphabet, AlphabetError};
+use memmap2::Mmap;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Can't read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{} is not valid UTF-8", path.display())]
+    Encoding { path: PathBuf },
+    #[error("{}:{line}: invalid word {text:?}", path.display())]
+    Malformed {
+        path: PathBuf,
+        line: usize,
+        text: String,
+    },
+    #[error("{} contains no words", path.display())]
+    Empty { path: PathBuf },
+    #[error("{}:{line}: {message}", path.display())]
+    Config {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    #[error("No word list given, and this build has no built-in lexicon")]
+    NoWordList,
+    #[error("Unknown lexicon {0:?}")]
+    UnknownLexicon(String),
+    #[error("{}: {source}", path.display())]
+    Index { path: PathBuf, source: IndexError },
+}
+
+/// The name of the lexicon described outside any section of the config
+/// file, which is the built-in lexicon unless the config says otherwise.
+pub const DEFAULT_NAME: &str = "default";
+
+/// Where to find the word list and popularity list.  Anything left out
+/// falls back to the lists built into the binary.  A precompiled index,
+/// if given, is used instead of both lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LexiconSource {
+    pub words: Option<PathBuf>,
+    pub popular: Option<PathBuf>,
+    pub index: Option<PathBuf>,
+    /// Overrides the rank below which words are rated as very popular.
+    pub popular_threshold: Option<usize>,
+    /// Overrides the rank below which the best anagram is called very
+    /// popular.
+    pub anagram_threshold: Option<usize>,
+    /// Whether accented letters are folded into plain ones.  An index
+    /// keeps the setting it was built with.
+    pub accents: Option<Accents>,
+    /// The tiles that words are split into.  An index keeps the alphabet
+    /// it was built with.
+    pub alphabet: Option<Alphabet>,
+}
+
+impl LexiconSource {
+    /// Fills in anything missing from `self` with the values in `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            words: self.words.or(other.words),
+            popular: self.popular.or(other.popular),
+            index: self.index.or(other.index),
+            popular_threshold: self.popular_threshold.or(other.popular_threshold),
+            anagram_threshold: self.anagram_threshold.or(other.anagram_threshold),
+            accents: self.accents.or(other.accents),
+            alphabet: self.alphabet.or(other.alphabet),
+        }
+    }
+}
+
+/// A set of named lexicons, and which one to use when no name is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexiconConfig {
+    pub default: String,
+    pub sources: BTreeMap<String, LexiconSource>,
+}
+
+impl Default for LexiconConfig {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_NAME.to_owned(),
+            sources: BTreeMap::from([(DEFAULT_NAME.to_owned(), LexiconSource::default())]),
+        }
+    }
+}
+
+impl LexiconConfig {
+    /// Reads a config file of `key = value` lines.  The keys `words`,
+    /// `popular` and `index` give the files for a lexicon,
+    /// `popular_threshold` and `anagram_threshold` override its ratings,
+    /// `accents` is `fold` or `distinct`, `alphabet` is `english`,
+    /// `spanish`, `welsh` or a list of tiles such as `a b c ch d`, and a
+    /// `[name]` line starts a new named lexicon.  Before the first section,
+    /// `default = name` chooses the lexicon to use when none is asked for.  Relative paths
+    /// are taken from the directory containing the config file.
+    pub fn from_config_file(path: &Path) -> Result<Self, LoadError> {
+        let text = read_text(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        parse_config(path, &text, base)
+    }
+
+    /// Overrides the files for the default lexicon.
+    pub fn with_default_source(mut self, source: LexiconSource) -> Self {
+        let current = self.sources.remove(&self.default).unwrap_or_default();
+        self.sources
+            .insert(self.default.clone(), source.or(current));
+        self
+    }
+
+    pub fn source(&self, name: Option<&str>) -> Result<&LexiconSource, LoadError> {
+        let name = name.unwrap_or(&self.default);
+        self.sources
+            .get(name)
+            .ok_or_else(|| LoadError::UnknownLexicon(name.to_owned()))
+    }
+
+    /// Loads one lexicon, or the default one if no name is given.
+    pub fn load(&self, name: Option<&str>) -> Result<Lexicon<'static>, LoadError> {
+        Lexicon::load_from(self.source(name)?)
+    }
+
+    /// Loads every lexicon.
+    pub fn load_all(&self) -> Result<Lexicons, LoadError> {
+        let mut lexicons = BTreeMap::new();
+        for (name, source) in &self.sources {
+            lexicons.insert(name.clone(), Lexicon::load_from(source)?);
+        }
+        Lexicons::new(lexicons, self.default.clone())
+    }
+}
+
+impl Lexicon<'static> {
+    /// Loads a lexicon from the files given in `source`, or from the
+    /// built-in lists if they are not given.
+    ///
+    /// The file contents live for the rest of the program, because entries
+    /// borrow their words from them.
+    pub fn load_from(source: &LexiconSource) -> Result<Self, LoadError> {
+        let lexicon = match &source.index {
+            Some(path) => load_index(path)?,
+            None => load_lists(source)?,
+        };
+        Ok(lexicon.with_thresholds(source.popular_threshold, source.anagram_threshold))
+    }
+}
+
+fn load_lists(source: &LexiconSource) -> Result<Lexicon<'static>, LoadError> {
+    let words = match &source.words {
+        Some(path) => read_list(path, is_valid_word)?,
+        None => embedded::WORDS.ok_or(LoadError::NoWordList)?,
+    };
+    let popular = match &source.popular {
+        Some(path) => Some(read_list(path, is_valid_popular_word)?),
+        None => embedded::POPULAR_WORDS,
+    };
+
+    let lexicon = Lexicon::new(
+        nonblank_lines(words),
+        source.alphabet.clone().unwrap_or_default(),
+        source.accents.unwrap_or_default(),
+    );
+    Ok(match popular {
+        Some(popular) => {
+            lexicon.with_popular_words(nonblank_lines(popular), DEFAULT_POPULAR_THRESHOLD)
+        }
+        None => lexicon,
+    })
+}
+
+#[cfg(feature = "embedded-lexicon")]
+mod embedded {
+    pub const WORDS: Option<&str> = Some(include_str!("../../lexicon/twl06.txt"));
+    pub const POPULAR_WORDS: Option<&str> = Some(include_str!("../../lexicon/tv2006.txt"));
+}
+
+#[cfg(not(feature = "embedded-lexicon"))]
+mod embedded {
+    pub const WORDS: Option<&str> = None;
+    pub const POPULAR_WORDS: Option<&str> = None;
+}
+
+/// Memory-maps an index file, which stays mapped for the rest of the
+/// program.
+fn load_index(path: &Path) -> Result<Lexicon<'static>, LoadError> {
+    let io_error = |source| LoadError::Io {
+        path: path.to_owned(),
+        source,
+    };
+    let file = fs::File::open(path).map_err(io_error)?;
+    // Safety: the map is read-only, and we assume nobody rewrites the index
+    // file while we're running.
+    let map = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+    let bytes: &'static [u8] = Box::leak(Box::new(map));
+    Lexicon::from_index(bytes).map_err(|source| LoadError::Index {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn nonblank_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+fn read_text(path: &Path) -> Result<String, LoadError> {
+    let bytes = fs::read(path).map_err(|source| LoadError::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    String::from_utf8(bytes).map_err(|_| LoadError::Encoding {
+        path: path.to_owned(),
+    })
+}
+
+/// Reads and checks a list of words, one per line, and keeps it in memory
+/// for the rest of the program.
+fn read_list(path: &Path, is_valid: fn(&str) -> bool) -> Result<&'static str, LoadError> {
+    let text = read_text(path)?;
+    check_list(path, &text, is_valid)?;
+    Ok(Box::leak(text.into_boxed_str()))
+}
+
+fn is_valid_word(word: &str) -> bool {
+    !word.chars().any(char::is_control) && word.chars().any(char::is_alphabetic)
+}
+
+/// Popular words may be followed by a tab and a frequency count.
+fn is_valid_popular_word(line: &str) -> bool {
+    match line.split_once('\t') {
+        Some((word, count)) => is_valid_word(word) && count.trim().parse::<u64>().is_ok(),
+        None => is_valid_word(line),
+    }
+}
+
+fn check_list(path: &Path, text: &str, is_valid: fn(&str) -> bool) -> Result<(), LoadError> {
+    let mut count = 0;
+    for (n, line) in text.lines().enumerate() {
+        let word = line.trim();
+        if word.is_empty() {
+            continue;
+        }
+        if !is_valid(word) {
+            return Err(LoadError::Malformed {
+                path: path.to_owned(),
+                line: n + 1,
+                text: line.to_owned(),
+            });
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return Err(LoadError::Empty {
+            path: path.to_owned(),
+        });
+    }
+    Ok(())
+}
+
+fn parse_config(path: &Path, text: &str, base: &Path) -> Result<LexiconConfig, LoadError> {
+    let mut config = LexiconConfig::default();
+    let mut section = DEFAULT_NAME.to_owned();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| LoadError::Config {
+            path: path.to_owned(),
+            line: n + 1,
+            message,
+        };
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| error("expected [name]".to_owned()))?;
+            section = name.to_owned();
+            config.sources.entry(section.clone()).or_default();
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected key = value".to_owned()))?;
+        let value = value.trim().trim_matches('"');
+        let parse_number = |value: &str| {
+            value
+                .parse()
+                .map_err(|_| error(format!("expected a number, not {value:?}")))
+        };
+        let source = config.sources.entry(section.clone()).or_default();
+        match key.trim() {
+            "words" => source.words = Some(base.join(value)),
+            "popular" => source.popular = Some(base.join(value)),
+            "index" => source.index = Some(base.join(value)),
+            "popular_threshold" => source.popular_threshold = Some(parse_number(value)?),
+            "anagram_threshold" => source.anagram_threshold = Some(parse_number(value)?),
+            "accents" => source.accents = Some(value.parse().map_err(error)?),
+            "alphabet" => {
+                source.alphabet = Some(
+                    value
+                        .parse()
+                        .map_err(|e: AlphabetError| error(e.to_string()))?,
+                )
+            }
+            "default" if section == DEFAULT_NAME => config.default = value.to_owned(),
+            key => return Err(error(format!("unknown key {key:?}"))),
+        }
+    }
+
+    if !config.sources.contains_key(&config.default) {
+        return Err(LoadError::UnknownLexicon(config.default));
+    }
+    // Without a built-in word list, the unnamed lexicon only exists if the
+    // config file gives it one.
+    if embedded::WORDS.is_none() && config.default != DEFAULT_NAME {
+        if let Some(source) = config.sources.get(DEFAULT_NAME) {
+            if source.words.is_none() {
+                config.sources.remove(DEFAULT_NAME);
+            }
+        }
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{check, let_assert};
+
+    #[test]
+    fn test_check_list() {
+        let path = Path::new("words.txt");
+        check!(check_list(path, "cat\n\ndog\nhot dog\n", is_valid_word).is_ok());
+        let_assert!(
+            Err(LoadError::Malformed { line, .. }) = check_list(path, "cat\n123\n", is_valid_word)
+        );
+        check!(line == 2);
+        let_assert!(Err(LoadError::Empty { .. }) = check_list(path, "\n \n", is_valid_word));
+    }
+
+    #[test]
+    fn test_check_popular_list() {
+        let path = Path::new("popular.txt");
+        check!(check_list(path, "the\t1000\ncat\t20\n", is_valid_popular_word).is_ok());
+        check!(check_list(path, "the\ncat\n", is_valid_popular_word).is_ok());
+        check!(check_list(path, "the\tlots\n", is_valid_popular_word).is_err());
+        check!(check_list(path, "the\t1000\n", is_valid_word).is_err());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let path = Path::new("/etc/wf.conf");
+        let text = "# Collins\nwords = collins.txt\npopular = \"/usr/share/pop.txt\"\nanagram_threshold = 500\naccents = distinct\nalphabet = spanish\n";
+        let config = parse_config(path, text, Path::new("/etc")).unwrap();
+        let source = config.source(None).unwrap();
+        check!(source.words == Some(PathBuf::from("/etc/collins.txt")));
+        check!(source.popular == Some(PathBuf::from("/usr/share/pop.txt")));
+        check!(source.popular_threshold == None);
+        check!(source.anagram_threshold == Some(500));
+        check!(source.accents == Some(Accents::Distinct));
+        check!(source.alphabet == Some("spanish".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_config_sections() {
+        let path = Path::new("/etc/wf.conf");
+        let text =
+            "default = collins\n[collins]\nwords = collins.txt\n\n[oxford]\nwords = oxford.txt\n";
+        let config = parse_config(path, text, Path::new("/etc")).unwrap();
+        check!(config.default == "collins");
+        check!(config.sources.keys().collect::<Vec<_>>() == ["collins", "default", "oxford"]);
+        let source = config.source(None).unwrap();
+        check!(source.words == Some(PathBuf::from("/etc/collins.txt")));
+        let source = config.source(Some("oxford")).unwrap();
+        check!(source.words == Some(PathBuf::from("/etc/oxford.txt")));
+        let_assert!(Err(LoadError::UnknownLexicon(name)) = config.source(Some("tv")));
+        check!(name == "tv");
+    }
+
+    #[test]
+    fn test_with_default_source() {
+        let config = LexiconConfig::default().with_default_source(LexiconSource {
+            words: Some(PathBuf::from("words.txt")),
+            ..Default::default()
+        });
+        check!(config.source(None).unwrap().words == Some(PathBuf::from("words.txt")));
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        let path = Path::new("wf.conf");
+        let_assert!(
+            Err(LoadError::Config { line, .. }) = parse_config(path, "words", Path::new(""))
+        );
+        check!(line == 1);
+        let_assert!(
+            Err(LoadError::Config { message, .. }) =
+                parse_config(path, "\nfoo = bar", Path::new(""))
+        );
+        check!(message == "unknown key \"foo\"");
+        let_assert!(
+            Err(LoadError::UnknownLexicon(name)) =
+                parse_config(path, "default = sowpods", Path::new(""))
+        );
+        check!(name == "sowpods");
+        check!(parse_config(path, "popular_threshold = lots", Path::new("")).is_err());
+        check!(parse_config(path, "accents = strip", Path::new("")).is_err());
+        check!(parse_config(path, "alphabet = a b a", Path::new("")).is_err());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let source = LexiconSource {
+            words: Some(PathBuf::from("/nonexistent/words.txt")),
+            ..Default::default()
+        };
+        let_assert!(Err(LoadError::Io { .. }) = Lexicon::load_from(&source));
+    }
+}
@@ -0,0 +1,145 @@
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// How a lexicon treats letters with accents, such as `é` or `ß`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Accents {
+    /// Accented letters count as the plain letters they're based on, so
+    /// `é` is `e` and `ß` is `ss`.
+    #[default]
+    Fold,
+    /// Accented letters are letters in their own right.  They match
+    /// themselves in patterns and text searches, but can't be made from a
+    /// rack of A to Z tiles.
+    Distinct,
+}
+
+impl Accents {
+    /// The form of `text` that letter checks and searches look at.
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Accents::Fold => fold(text),
+            Accents::Distinct => Cow::Borrowed(text),
+        }
+    }
+
+    pub(crate) fn to_u32(self) -> u32 {
+        self as u32
+    }
+
+    pub(crate) fn from_u32(n: u32) -> Option<Self> {
+        match n {
+            0 => Some(Accents::Fold),
+            1 => Some(Accents::Distinct),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Accents {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fold" => Ok(Accents::Fold),
+            "distinct" => Ok(Accents::Distinct),
+            _ => Err(format!("expected fold or distinct, not {s:?}")),
+        }
+    }
+}
+
+impl Display for Accents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Accents::Fold => write!(f, "fold"),
+            Accents::Distinct => write!(f, "distinct"),
+        }
+    }
+}
+
+/// Strips accents from letters, and spells out letters such as `ß` and
+/// `æ` that don't decompose.  Everything else is left alone, so letters
+/// from other scripts stay as they are.
+pub fn fold(text: &str) -> Cow<'_, str> {
+    fold_except(text, &[])
+}
+
+/// Folds accents like `fold`, except for the lowercase letters in `keep`
+/// and their capitals, such as the `ñ` of a Spanish alphabet.
+pub fn fold_except<'a>(text: &'a str, keep: &[char]) -> Cow<'a, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut folded = String::with_capacity(text.len());
+    for ch in text.nfc() {
+        if keep.contains(&ch)
+            || ch
+                .to_lowercase()
+                .next()
+                .is_some_and(|ch| keep.contains(&ch))
+        {
+            folded.push(ch);
+        } else {
+            ch.nfd()
+                .filter(|&ch| !is_combining_mark(ch))
+                .for_each(|ch| fold_char(ch, &mut folded));
+        }
+    }
+    Cow::Owned(folded)
+}
+
+fn fold_char(ch: char, folded: &mut String) {
+    match ch {
+        'ß' => folded.push_str("ss"),
+        'ẞ' => folded.push_str("SS"),
+        'æ' => folded.push_str("ae"),
+        'Æ' => folded.push_str("AE"),
+        'œ' => folded.push_str("oe"),
+        'Œ' => folded.push_str("OE"),
+        'þ' => folded.push_str("th"),
+        'Þ' => folded.push_str("TH"),
+        'ø' => folded.push('o'),
+        'Ø' => folded.push('O'),
+        'đ' | 'ð' => folded.push('d'),
+        'Đ' | 'Ð' => folded.push('D'),
+        'ł' => folded.push('l'),
+        'Ł' => folded.push('L'),
+        'ı' => folded.push('i'),
+        ch => folded.push(ch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    #[test]
+    fn test_fold() {
+        check!(fold("cafe") == "cafe");
+        check!(fold("café") == "cafe");
+        check!(fold("Straße") == "Strasse");
+        check!(fold("niño") == "nino");
+        check!(fold("Œuvre") == "OEuvre");
+        check!(fold("smørrebrød") == "smorrebrod");
+        // Decomposed accents fold the same way as precomposed ones.
+        check!(fold("cafe\u{301}") == "cafe");
+        check!(fold("αβγ") == "αβγ");
+        check!(fold_except("Niño año", &['ñ']) == "Niño año");
+        check!(fold_except("Ñandú", &['ñ']) == "Ñandu");
+        check!(fold_except("an\u{303}o", &['ñ']) == "año");
+    }
+
+    #[test]
+    fn test_normalize() {
+        check!(Accents::Fold.normalize("crème") == "creme");
+        check!(Accents::Distinct.normalize("crème") == "crème");
+        check!("fold".parse() == Ok(Accents::Fold));
+        check!("folded".parse::<Accents>().is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use super::LetterMask;
+use std::fmt::Display;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Every letter from A to Z.
+const ANY: LetterMask = LetterMask((1 << 26) - 1);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PatternError {
+    #[error("Empty pattern")]
+    Empty,
+    #[error("Unexpected {ch:?} at position {pos}")]
+    UnexpectedChar { ch: char, pos: usize },
+    #[error("Unclosed [ at position {pos}")]
+    Unclosed { pos: usize },
+    #[error("Empty letter class at position {pos}")]
+    EmptyClass { pos: usize },
+}
+
+/// A crossword-style pattern, with one set of allowed letters for each
+/// position in the word.  `C?T??E` and `.A.E` use `?` or `.` for any
+/// letter, and `[AEIOU]`, `[^AEIOU]` or `[A-F]` allow a class of letters.
+/// Only letters count as positions, so `.A.E` matches both "gaze" and
+/// "ga-ze", and spaces, hyphens and apostrophes in a pattern are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    positions: Vec<LetterMask>,
+}
+
+impl Pattern {
+    /// The number of letters in a matching word.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        let mut letters = word.chars().filter(|ch| ch.is_alphabetic());
+        for &mask in &self.positions {
+            let Some(ch) = letters.next() else {
+                return false;
+            };
+            if mask == ANY {
+                continue;
+            }
+            if !ch.is_ascii_alphabetic() || mask.0 & bit(ch) == 0 {
+                return false;
+            }
+        }
+        letters.next().is_none()
+    }
+}
+
+fn bit(ch: char) -> u32 {
+    1 << (ch.to_ascii_lowercase() as u32 - 'a' as u32)
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut positions = Vec::new();
+        let mut chars = s.chars().enumerate().peekable();
+        while let Some((pos, ch)) = chars.next() {
+            let mask = match ch {
+                '?' | '.' => ANY,
+                ' ' | '-' | '\'' => continue,
+                ch if ch.is_ascii_alphabetic() => LetterMask(bit(ch)),
+                '[' => {
+                    let negated = chars.next_if(|&(_, ch)| ch == '^' || ch == '!').is_some();
+                    let mut mask = 0;
+                    loop {
+                        match chars.next() {
+                            Some((_, ']')) => break,
+                            Some((_, first)) if first.is_ascii_alphabetic() => {
+                                let last = match chars.next_if(|&(_, ch)| ch == '-') {
+                                    Some((pos, _)) => match chars.next() {
+                                        Some((_, last)) if last.is_ascii_alphabetic() => last,
+                                        Some((pos, ch)) => {
+                                            return Err(PatternError::UnexpectedChar { ch, pos })
+                                        }
+                                        None => return Err(PatternError::Unclosed { pos }),
+                                    },
+                                    None => first,
+                                };
+                                let (first, last) =
+                                    (first.to_ascii_lowercase(), last.to_ascii_lowercase());
+                                for ch in first..=last {
+                                    mask |= bit(ch);
+                                }
+                            }
+                            Some((pos, ch)) => {
+                                return Err(PatternError::UnexpectedChar { ch, pos })
+                            }
+                            None => return Err(PatternError::Unclosed { pos }),
+                        }
+                    }
+                    if negated {
+                        mask = !mask & ANY.0;
+                    }
+                    if mask == 0 {
+                        return Err(PatternError::EmptyClass { pos });
+                    }
+                    LetterMask(mask)
+                }
+                ch => return Err(PatternError::UnexpectedChar { ch, pos }),
+            };
+            positions.push(mask);
+        }
+        if positions.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        Ok(Self { positions })
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for mask in &self.positions {
+            let letters = ('a'..='z').filter(|&ch| mask.0 & bit(ch) != 0);
+            match mask.0.count_ones() {
+                26 => write!(f, "?")?,
+                1 => write!(f, "{}", letters.collect::<String>())?,
+                _ => write!(f, "[{}]", letters.collect::<String>())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    fn pattern(s: &str) -> Pattern {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        check!(pattern("C?T??E").to_string() == "c?t??e");
+        check!(pattern(".A.E").len() == 4);
+        check!(pattern("ICE CREAM").len() == 8);
+        check!(pattern("[AEIOU]x").to_string() == "[aeiou]x");
+        check!(pattern("[^aeiou]").to_string() == "[bcdfghjklmnpqrstvwxyz]");
+        check!(pattern("[a-d]").to_string() == "[abcd]");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        check!("".parse::<Pattern>() == Err(PatternError::Empty));
+        check!("ab1".parse::<Pattern>() == Err(PatternError::UnexpectedChar { ch: '1', pos: 2 }));
+        check!("a[bc".parse::<Pattern>() == Err(PatternError::Unclosed { pos: 1 }));
+        check!("[^a-z]".parse::<Pattern>() == Err(PatternError::EmptyClass { pos: 0 }));
+        check!("[a-]".parse::<Pattern>() == Err(PatternError::UnexpectedChar { ch: ']', pos: 3 }));
+    }
+
+    #[test]
+    fn test_matches() {
+        check!(pattern("C?T??E").matches("cattle"));
+        check!(pattern("C?T??E").matches("Cat-tle"));
+        check!(!pattern("C?T??E").matches("cattles"));
+        check!(!pattern("C?T??E").matches("cat"));
+        check!(pattern(".a.e").matches("gaze"));
+        check!(pattern("[aeiou]x.").matches("axe"));
+        check!(!pattern("[aeiou]x.").matches("sxe"));
+        check!(pattern("?").matches("é"));
+        check!(!pattern("e").matches("é"));
+    }
+}
@@ -0,0 +1,423 @@
+//! A small query language for filters, such as
+//!
+//! ```text
+//! len:7 (has:q OR has:z) NOT pattern:??????s
+//! ```
+//!
+//! Terms are `key:value` pairs.  Terms next to each other must all match,
+//! `OR` (or `|`) between them means either may match, and `NOT` (or a
+//! leading `-` or `!`) negates a term.  Parentheses group terms.  `AND` (or
+//! `&`) may be written out, but isn't needed.  `AND` binds more tightly
+//! than `OR`.
+//!
+//! The keys are:
+//!
+//! ```text
+//! len:4, len:4-6     number of letters
+//! has:qu             contains these letters
+//! from:retain?       can be made from these letters, with ? for a blank
+//! include:abc        uses each of these letters at least once
+//! exclude:xyz        uses none of these letters
+//! pattern:C?T??E     fits this crossword pattern
+//! starts:un          starts with this text
+//! ends:ing           ends with this text
+//! sub:qu             contains this text somewhere
+//! count:e=2+         has at least two Es (or `e=1` for exactly one, or
+//!                    `e=1-3` for one to three)
+//! enum:3,5           a phrase with this many letters in each word (or
+//!                    `enum:3-3` for words joined by a hyphen)
+//! vowels:2, vowels:1-3
+//!                    number of vowels, not counting Y
+//! consonants:5       number of consonants, counting Y
+//! shape:CVCCVC       where the vowels (V) and consonants (C) are, with ?
+//!                    for either
+//! yvowels:2          (and ycons:, yshape:) the same, but counting Y as a
+//!                    vowel
+//! popular:high       rated at least this popular (low, medium or high)
+//! rank:5000          ranked this high or higher in the popularity list
+//! oneword:yes        is (or with `no`, isn't) a single word
+//! ```
+
+use super::filter::Check;
+use super::{Alphabet, LengthRange, Pattern};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The deepest that parentheses and NOTs may nest, so that a silly query
+/// can't overflow the stack.
+const MAX_DEPTH: usize = 200;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueryError {
+    #[error("Empty query")]
+    Empty,
+    #[error("Unexpected end of query at position {pos}")]
+    UnexpectedEnd { pos: usize },
+    #[error("Unexpected {token:?} at position {pos}")]
+    Unexpected { token: String, pos: usize },
+    #[error("Expected key:value at position {pos}, not {token:?}")]
+    NotATerm { token: String, pos: usize },
+    #[error("Unknown key {key:?} at position {pos}")]
+    UnknownKey { key: String, pos: usize },
+    #[error("Bad value for {key} at position {pos}: {message}")]
+    BadValue {
+        key: String,
+        pos: usize,
+        message: String,
+    },
+    #[error("Unclosed ( at position {pos}")]
+    Unclosed { pos: usize },
+    #[error("Query nests too deeply at position {pos}")]
+    TooDeep { pos: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(&'a str),
+}
+
+/// Splits a query into tokens, each with its position in characters.
+fn tokenize(s: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().enumerate().peekable();
+    while let Some((pos, (start, ch))) = chars.next() {
+        let token = match ch {
+            ch if ch.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '-' | '!' => Token::Not,
+            _ => {
+                let mut end = start + ch.len_utf8();
+                while let Some((_, (i, ch))) =
+                    chars.next_if(|&(_, (_, ch))| !ch.is_whitespace() && ch != '(' && ch != ')')
+                {
+                    end = i + ch.len_utf8();
+                }
+                match &s[start..end] {
+                    "|" => Token::Or,
+                    "&" => Token::And,
+                    word if word.eq_ignore_ascii_case("or") => Token::Or,
+                    word if word.eq_ignore_ascii_case("and") => Token::And,
+                    word if word.eq_ignore_ascii_case("not") => Token::Not,
+                    word => Token::Word(word),
+                }
+            }
+        };
+        tokens.push((pos, token));
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    alphabet: &'a Alphabet,
+    pos: usize,
+    /// The length of the query, in characters.
+    end: usize,
+    /// How many parentheses and NOTs enclose the current token.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|&(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<(usize, Token<'a>)> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn unexpected(&self, pos: usize, token: Token) -> QueryError {
+        let token = match token {
+            Token::Open => "(",
+            Token::Close => ")",
+            Token::And => "AND",
+            Token::Or => "OR",
+            Token::Not => "NOT",
+            Token::Word(word) => word,
+        };
+        QueryError::Unexpected {
+            token: token.to_owned(),
+            pos,
+        }
+    }
+
+    fn enter(&mut self, pos: usize) -> Result<(), QueryError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(QueryError::TooDeep { pos });
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Check, QueryError> {
+        let mut checks = vec![self.parse_and()?];
+        while self.peek() == Some(Token::Or) {
+            self.next();
+            checks.push(self.parse_and()?);
+        }
+        Ok(combine(checks, Check::Any))
+    }
+
+    fn parse_and(&mut self) -> Result<Check, QueryError> {
+        let mut checks = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Open | Token::Not | Token::Word(_)) => {}
+                _ => break,
+            }
+            checks.push(self.parse_unary()?);
+        }
+        Ok(combine(checks, Check::All))
+    }
+
+    fn parse_unary(&mut self) -> Result<Check, QueryError> {
+        match self.next() {
+            Some((pos, Token::Not)) => {
+                self.enter(pos)?;
+                let check = self.parse_unary()?;
+                self.depth -= 1;
+                Ok(Check::Not(Box::new(check)))
+            }
+            Some((pos, Token::Open)) => {
+                self.enter(pos)?;
+                let check = self.parse_or()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some((_, Token::Close)) => Ok(check),
+                    Some((pos, token)) => Err(self.unexpected(pos, token)),
+                    None => Err(QueryError::Unclosed { pos }),
+                }
+            }
+            Some((pos, Token::Word(word))) => parse_term(word, pos, self.alphabet),
+            Some((pos, token)) => Err(self.unexpected(pos, token)),
+            None => Err(QueryError::UnexpectedEnd { pos: self.end }),
+        }
+    }
+}
+
+fn combine(mut checks: Vec<Check>, make: fn(Vec<Check>) -> Check) -> Check {
+    if checks.len() == 1 {
+        checks.pop().unwrap()
+    } else {
+        make(checks)
+    }
+}
+
+fn parse_term(word: &str, pos: usize, alphabet: &Alphabet) -> Result<Check, QueryError> {
+    let Some((key, value)) = word.split_once(':') else {
+        return Err(QueryError::NotATerm {
+            token: word.to_owned(),
+            pos,
+        });
+    };
+    let bad_value = |message: String| QueryError::BadValue {
+        key: key.to_owned(),
+        pos: pos + key.chars().count() + 1,
+        message,
+    };
+    if value.is_empty() {
+        return Err(bad_value("missing value".to_owned()));
+    }
+    let letters = || match value.chars().find(|&ch| !ch.is_alphabetic()) {
+        Some(ch) => Err(bad_value(format!("{ch:?} is not a letter"))),
+        None => Ok(value),
+    };
+    let not_a_tile = |ch: char| bad_value(format!("{ch:?} is not on any tile"));
+    Ok(match key.to_ascii_lowercase().as_str() {
+        "len" | "length" => Check::Length(value.parse::<LengthRange>().map_err(bad_value)?),
+        "has" | "contains" => Check::Contains(alphabet.letters(letters()?).map_err(not_a_tile)?),
+        "from" | "contained" => {
+            if let Some(ch) = value.chars().find(|&ch| !ch.is_alphabetic() && ch != '?') {
+                return Err(bad_value(format!("{ch:?} is not a letter or blank")));
+            }
+            Check::Contained(alphabet.letters(value).map_err(not_a_tile)?)
+        }
+        "include" => Check::IncludeLetters(alphabet.mask(letters()?).map_err(not_a_tile)?),
+        "exclude" => Check::ExcludeLetters(alphabet.mask(letters()?).map_err(not_a_tile)?),
+        "pattern" => Check::Pattern(
+            value
+                .parse::<Pattern>()
+                .map_err(|e| bad_value(e.to_string()))?,
+        ),
+        "starts" | "prefix" => Check::Prefix(value.to_lowercase()),
+        "ends" | "suffix" => Check::Suffix(value.to_lowercase()),
+        "sub" | "substring" => Check::Substring(value.to_lowercase()),
+        "count" => Check::LetterCount(value.parse().map_err(bad_value)?),
+        "enum" | "enumeration" => Check::Enumeration(value.parse().map_err(bad_value)?),
+        "vowels" => Check::Vowels {
+            range: value.parse().map_err(bad_value)?,
+            y_is_vowel: false,
+        },
+        "yvowels" => Check::Vowels {
+            range: value.parse().map_err(bad_value)?,
+            y_is_vowel: true,
+        },
+        "cons" | "consonants" => Check::Consonants {
+            range: value.parse().map_err(bad_value)?,
+            y_is_vowel: false,
+        },
+        "ycons" | "yconsonants" => Check::Consonants {
+            range: value.parse().map_err(bad_value)?,
+            y_is_vowel: true,
+        },
+        "shape" => Check::Shape {
+            shape: value.parse().map_err(bad_value)?,
+            y_is_vowel: false,
+        },
+        "yshape" => Check::Shape {
+            shape: value.parse().map_err(bad_value)?,
+            y_is_vowel: true,
+        },
+        "popular" => Check::MinPopularity(value.parse().map_err(bad_value)?),
+        "rank" => Check::MaxRank(
+            value
+                .parse()
+                .map_err(|_| bad_value("expected a number".to_owned()))?,
+        ),
+        "oneword" => match value.to_ascii_lowercase().as_str() {
+            "yes" | "true" => Check::SingleWord(true),
+            "no" | "false" => Check::SingleWord(false),
+            _ => return Err(bad_value("expected yes or no".to_owned())),
+        },
+        _ => {
+            return Err(QueryError::UnknownKey {
+                key: key.to_owned(),
+                pos,
+            })
+        }
+    })
+}
+
+impl Check {
+    /// Parses a query in the language described at the top of this module,
+    /// reading letters as tiles of `alphabet`.
+    pub fn parse(s: &str, alphabet: &Alphabet) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(s),
+            alphabet,
+            pos: 0,
+            end: s.chars().count(),
+            depth: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err(QueryError::Empty);
+        }
+        let check = parser.parse_or()?;
+        match parser.next() {
+            Some((pos, token)) => Err(parser.unexpected(pos, token)),
+            None => Ok(check),
+        }
+    }
+}
+
+/// Parses a query with the English alphabet.
+impl FromStr for Check {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Check::parse(s, &Alphabet::english())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexi::{Filter, Lexicon};
+    use assert2::{check, let_assert};
+
+    fn search(query: &str, words: &[&'static str]) -> Vec<String> {
+        let filter = Filter::new(vec![query.parse().unwrap()]);
+        let lex = Lexicon::from_iter(words.iter().copied());
+        lex.matching_words(&filter).map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn test_and_or_not() {
+        let words = [
+            "quartz", "quartet", "zombies", "zealots", "jackpot", "quicken",
+        ];
+        check!(
+            search("len:7 (has:q OR has:z) NOT pattern:??????s", &words) == ["quartet", "quicken"]
+        );
+        check!(search("has:q | has:j -len:7", &words) == ["quartz", "quartet", "quicken"]);
+        check!(search("len:7 (has:q OR has:z) -ends:s", &words) == ["quartet", "quicken"]);
+        check!(search("starts:qu sub:art", &words) == ["quartz", "quartet"]);
+        let phrases = ["ice cream", "ice-cold", "iced tea", "icecream"];
+        check!(search("enum:3,5 | enum:3-4", &phrases) == ["ice cream", "ice-cold"]);
+        check!(search("starts:icec", &phrases) == ["ice cream", "ice-cold", "icecream"]);
+    }
+
+    #[test]
+    fn test_precedence() {
+        let words = ["cat", "dog", "cow"];
+        // AND binds more tightly than OR.
+        check!(search("has:d OR has:c AND has:w", &words) == ["dog", "cow"]);
+        check!(search("(has:d OR has:c) AND has:o", &words) == ["dog", "cow"]);
+        check!(search("NOT NOT has:t", &words) == ["cat"]);
+        check!(search("from:ow?", &words) == ["cow"]);
+    }
+
+    #[test]
+    fn test_vowels() {
+        let words = ["rhythm", "crypt", "strand", "bandit", "rhymed"];
+        check!(search("vowels:0", &words) == ["rhythm", "crypt"]);
+        check!(search("yvowels:1 cons:4-5", &words) == ["crypt", "strand"]);
+        check!(search("shape:CVCCVC", &words) == ["bandit"]);
+        check!(search("yshape:CCVCVC", &words) == ["rhymed"]);
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let spanish = "spanish".parse::<Alphabet>().unwrap();
+        let lex = Lexicon::new(
+            ["chorro", "cohete", "carro"],
+            spanish.clone(),
+            Default::default(),
+        );
+        let search = |query: &str| {
+            let filter = Filter::new(vec![Check::parse(query, &spanish).unwrap()]);
+            lex.matching_words(&filter)
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+        check!(search("has:ch") == ["chorro"]);
+        check!(search("has:c") == ["cohete", "carro"]);
+        check!(search("include:rr") == ["chorro", "carro"]);
+        check!(search("from:rrchoo") == ["chorro"]);
+        let_assert!(Err(QueryError::BadValue { .. }) = Check::parse("has:ç", &spanish));
+    }
+
+    #[test]
+    fn test_errors() {
+        check!("".parse::<Check>().err() == Some(QueryError::Empty));
+        check!("has:q OR".parse::<Check>().err() == Some(QueryError::UnexpectedEnd { pos: 8 }));
+        let_assert!(Err(QueryError::Unclosed { pos: 6 }) = "len:7 (has:q".parse::<Check>());
+        let_assert!(Err(QueryError::Unexpected { pos: 5, .. }) = "has:q)".parse::<Check>());
+        let_assert!(Err(QueryError::NotATerm { pos: 6, .. }) = "has:q quartz".parse::<Check>());
+        let_assert!(Err(QueryError::UnknownKey { pos: 0, .. }) = "end:s".parse::<Check>());
+        let_assert!(Err(QueryError::BadValue { pos: 4, .. }) = "len:x".parse::<Check>());
+        let_assert!(Err(QueryError::BadValue { pos: 10, .. }) = "len:4 has:q1".parse::<Check>());
+    }
+
+    #[test]
+    fn test_depth() {
+        let nested = |depth: usize| format!("{}has:q{}", "(".repeat(depth), ")".repeat(depth));
+        let_assert!(Ok(_) = nested(MAX_DEPTH).parse::<Check>());
+        let_assert!(Err(QueryError::TooDeep { pos }) = nested(MAX_DEPTH + 1).parse::<Check>());
+        check!(pos == MAX_DEPTH);
+        let_assert!(Err(QueryError::TooDeep { .. }) = nested(20000).parse::<Check>());
+        let_assert!(Err(QueryError::TooDeep { .. }) = "-".repeat(20000).parse::<Check>());
+        check!(search(&format!("{}has:t", "NOT ".repeat(100)), &["cat", "dog"]) == ["cat"]);
+    }
+}
@@ -0,0 +1,65 @@
+use super::Lexicon;
+use super::LoadError;
+use std::collections::BTreeMap;
+
+/// A collection of lexicons, looked up by name.
+pub struct Lexicons {
+    lexicons: BTreeMap<String, Lexicon<'static>>,
+    default: String,
+}
+
+impl Lexicons {
+    pub fn new(
+        lexicons: BTreeMap<String, Lexicon<'static>>,
+        default: String,
+    ) -> Result<Self, LoadError> {
+        if !lexicons.contains_key(&default) {
+            return Err(LoadError::UnknownLexicon(default));
+        }
+        Ok(Self { lexicons, default })
+    }
+
+    /// Finds a lexicon by name, or the default lexicon if no name is given.
+    pub fn get(&self, name: Option<&str>) -> Result<&Lexicon<'static>, LoadError> {
+        let name = name.unwrap_or(&self.default);
+        self.lexicons
+            .get(name)
+            .ok_or_else(|| LoadError::UnknownLexicon(name.to_owned()))
+    }
+
+    pub fn default_name(&self) -> &str {
+        &self.default
+    }
+
+    /// Iterates over the lexicons in order of name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Lexicon<'static>)> {
+        self.lexicons
+            .iter()
+            .map(|(name, lexi)| (name.as_str(), lexi))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::{check, let_assert};
+
+    #[test]
+    fn test_get() {
+        let lexicons = Lexicons::new(
+            BTreeMap::from([
+                ("twl".to_owned(), Lexicon::from_iter(["cat", "dog"])),
+                (
+                    "collins".to_owned(),
+                    Lexicon::from_iter(["cat", "dog", "qi"]),
+                ),
+            ]),
+            "twl".to_owned(),
+        )
+        .unwrap();
+        check!(lexicons.get(None).unwrap().len() == 2);
+        check!(lexicons.get(Some("collins")).unwrap().len() == 3);
+        let_assert!(Err(LoadError::UnknownLexicon(name)) = lexicons.get(Some("oxford")));
+        check!(name == "oxford");
+    }
+}
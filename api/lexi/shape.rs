@@ -0,0 +1,163 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Shapes can only describe this many letters.
+pub const MAX_SHAPE_LEN: usize = 64;
+
+/// Where the vowels are in a word.  Y is tracked separately, because it
+/// can count either way.  Positions past `MAX_SHAPE_LEN` aren't recorded,
+/// but they are counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Vowels {
+    positions: u64,
+    y_positions: u64,
+    count: u16,
+    ys: u16,
+}
+
+impl Vowels {
+    /// Finds the vowels in the letters of a word, ignoring anything that
+    /// isn't a letter.
+    pub fn new(word: &str) -> Self {
+        let mut vowels = Self::default();
+        for (pos, ch) in word.chars().filter(|ch| ch.is_alphabetic()).enumerate() {
+            let bit = if pos < MAX_SHAPE_LEN { 1 << pos } else { 0 };
+            match ch.to_ascii_lowercase() {
+                'a' | 'e' | 'i' | 'o' | 'u' => {
+                    vowels.positions |= bit;
+                    vowels.count += 1;
+                }
+                'y' => {
+                    vowels.y_positions |= bit;
+                    vowels.ys += 1;
+                }
+                _ => {}
+            }
+        }
+        vowels
+    }
+
+    pub fn count(&self, y_is_vowel: bool) -> usize {
+        self.count as usize + if y_is_vowel { self.ys as usize } else { 0 }
+    }
+
+    /// A bit for each vowel, with the first letter in the lowest bit.
+    pub fn positions(&self, y_is_vowel: bool) -> u64 {
+        self.positions | if y_is_vowel { self.y_positions } else { 0 }
+    }
+
+    pub(crate) fn to_parts(self) -> (u64, u64, u16, u16) {
+        (self.positions, self.y_positions, self.count, self.ys)
+    }
+
+    pub(crate) fn from_parts((positions, y_positions, count, ys): (u64, u64, u16, u16)) -> Self {
+        Self {
+            positions,
+            y_positions,
+            count,
+            ys,
+        }
+    }
+}
+
+/// A vowel/consonant skeleton such as `CVCCVC`, where `?` or `.` allows
+/// either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shape {
+    len: usize,
+    vowels: u64,
+    consonants: u64,
+}
+
+impl Shape {
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks the shape against a word's vowels.  The word must already be
+    /// known to have the right number of letters.
+    pub fn matches(&self, vowels: &Vowels, y_is_vowel: bool) -> bool {
+        let positions = vowels.positions(y_is_vowel);
+        positions & self.vowels == self.vowels && positions & self.consonants == 0
+    }
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut shape = Shape {
+            len: 0,
+            vowels: 0,
+            consonants: 0,
+        };
+        for (pos, ch) in s.chars().enumerate() {
+            if pos >= MAX_SHAPE_LEN {
+                return Err(format!("Shape is longer than {MAX_SHAPE_LEN} letters"));
+            }
+            match ch.to_ascii_uppercase() {
+                'V' => shape.vowels |= 1 << pos,
+                'C' => shape.consonants |= 1 << pos,
+                '?' | '.' => {}
+                ch => {
+                    return Err(format!(
+                        "Invalid shape: {ch:?} at position {pos} is not C, V or ?"
+                    ))
+                }
+            }
+            shape.len += 1;
+        }
+        if shape.len == 0 {
+            return Err("Empty shape".to_owned());
+        }
+        Ok(shape)
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for pos in 0..self.len {
+            let ch = if self.vowels & 1 << pos != 0 {
+                'V'
+            } else if self.consonants & 1 << pos != 0 {
+                'C'
+            } else {
+                '?'
+            };
+            write!(f, "{ch}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    #[test]
+    fn test_vowels() {
+        let vowels = Vowels::new("Rhythm-ic");
+        check!(vowels.count(false) == 1);
+        check!(vowels.count(true) == 2);
+        check!(vowels.positions(false) == 0b1000000);
+        check!(vowels.positions(true) == 0b1000100);
+    }
+
+    #[test]
+    fn test_shape() {
+        let shape: Shape = "cvccvc".parse().unwrap();
+        check!(shape.to_string() == "CVCCVC");
+        check!(shape.matches(&Vowels::new("banter"), false));
+        check!(!shape.matches(&Vowels::new("beaten"), false));
+        check!(!shape.matches(&Vowels::new("byrnes"), false));
+        check!(shape.matches(&Vowels::new("byrnes"), true));
+        check!("CV?"
+            .parse::<Shape>()
+            .unwrap()
+            .matches(&Vowels::new("tax"), false));
+        check!("CVX".parse::<Shape>().is_err());
+        check!("".parse::<Shape>().is_err());
+    }
+}
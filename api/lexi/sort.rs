@@ -0,0 +1,105 @@
+use super::Entry;
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+/// What to sort search results by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Alphabetical order.
+    Alpha,
+    /// Number of letters.
+    Length,
+    /// Popularity rank, most popular first.  Unranked words come last.
+    Rank,
+    /// Frequency score, least frequent first.  Unscored words come first.
+    Score,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// How to order search results.  Without a key, they stay in lexicon
+/// order (or the reverse of it).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sort {
+    pub key: Option<SortKey>,
+    pub order: SortOrder,
+}
+
+impl Sort {
+    pub fn new(key: Option<SortKey>, order: SortOrder) -> Self {
+        Self { key, order }
+    }
+
+    /// Sorts entries in place.  Entries that compare equal keep their
+    /// order, so ties stay in lexicon order.
+    pub fn sort(&self, entries: &mut [&Entry]) {
+        if let Some(key) = self.key {
+            entries.sort_by(|a, b| {
+                let ordering = compare(key, a, b);
+                match self.order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            });
+        } else if self.order == SortOrder::Desc {
+            entries.reverse();
+        }
+    }
+}
+
+fn compare(key: SortKey, a: &Entry, b: &Entry) -> Ordering {
+    match key {
+        SortKey::Alpha => a.word.cmp(b.word),
+        SortKey::Length => a.len.cmp(&b.len),
+        SortKey::Rank => a
+            .rank
+            .unwrap_or(usize::MAX)
+            .cmp(&b.rank.unwrap_or(usize::MAX)),
+        SortKey::Score => a.frequency.cmp(&b.frequency),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexi::Lexicon;
+    use assert2::check;
+
+    fn sorted(lex: &Lexicon, key: Option<SortKey>, order: SortOrder) -> Vec<String> {
+        let mut entries = lex.entries().collect::<Vec<_>>();
+        Sort::new(key, order).sort(&mut entries);
+        entries.iter().map(|e| e.word().to_owned()).collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        let lex = Lexicon::from_iter(["pear", "fig", "apple", "kiwi", "banana"])
+            .with_popular_words(["apple\t300", "pear\t10", "fig\t20"], 10);
+
+        check!(sorted(&lex, None, SortOrder::Asc) == ["pear", "fig", "apple", "kiwi", "banana"]);
+        check!(sorted(&lex, None, SortOrder::Desc) == ["banana", "kiwi", "apple", "fig", "pear"]);
+        check!(
+            sorted(&lex, Some(SortKey::Alpha), SortOrder::Asc)
+                == ["apple", "banana", "fig", "kiwi", "pear"]
+        );
+        check!(
+            sorted(&lex, Some(SortKey::Length), SortOrder::Desc)
+                == ["banana", "apple", "pear", "kiwi", "fig"]
+        );
+        check!(
+            sorted(&lex, Some(SortKey::Rank), SortOrder::Asc)
+                == ["apple", "fig", "pear", "kiwi", "banana"]
+        );
+        check!(
+            sorted(&lex, Some(SortKey::Score), SortOrder::Desc)
+                == ["apple", "fig", "pear", "kiwi", "banana"]
+        );
+    }
+}
@@ -1,45 +1,114 @@
+use super::alphabet::MAX_TILES;
 use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The character that stands for a blank tile, which can be any letter.
+pub const BLANK: char = '?';
+
+/// A bag of tiles: how many of each tile in an alphabet, plus blanks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct SortedLetters {
-    letters: [u8; 26],
+    letters: [u8; MAX_TILES],
+    blanks: u8,
 }
 
 impl SortedLetters {
+    /// Counts the letters A to Z and the blanks, as tiles of the English
+    /// alphabet.  Anything else is ignored, so accents have to be folded
+    /// first if they should count.  Use `Alphabet::letters` for other
+    /// alphabets.
     pub fn from_word(letters: &str) -> Self {
-        let mut sorted = [0; 26];
+        let mut sorted = [0; MAX_TILES];
+        let mut blanks = 0;
         for ch in letters.chars() {
             if ch.is_ascii_alphabetic() {
                 sorted[ch.to_ascii_lowercase() as usize - 'a' as usize] += 1;
+            } else if ch == BLANK {
+                blanks += 1;
             }
         }
-        Self { letters: sorted }
+        Self {
+            letters: sorted,
+            blanks,
+        }
     }
 
+    /// True if every letter in `other` can be made from these letters,
+    /// using blanks for any that are missing.
     pub fn is_superset(&self, other: &Self) -> bool {
-        self.letters
-            .iter()
-            .zip(other.letters.iter())
-            .all(|(a, b)| a >= b)
+        other.is_subset(self)
     }
 
+    /// True if these letters can all be made from `other`, using its blanks
+    /// for any that are missing.
     pub fn is_subset(&self, other: &Self) -> bool {
-        self.letters
+        let missing = self
+            .letters
             .iter()
             .zip(other.letters.iter())
-            .all(|(a, b)| a <= b)
+            .map(|(&a, &b)| a.saturating_sub(b) as usize)
+            .sum::<usize>();
+        missing <= other.blanks as usize && self.len() <= other.len()
     }
 
     pub fn minus(&self, other: &Self) -> Option<Self> {
-        let mut sorted = self.letters;
-        for (i, a) in sorted.iter_mut().enumerate() {
-            *a = a.checked_sub(other.letters[i])?;
+        self.minus_with_blanks(other).map(|(rest, _)| rest)
+    }
+
+    /// Takes the letters in `other` away from these, returning what's left
+    /// and the letters that blanks stood for.  Blanks here make up for
+    /// letters that are missing, while blanks in `other` take away the
+    /// first letters left over, in alphabetical order.
+    pub fn minus_with_blanks(&self, other: &Self) -> Option<(Self, Self)> {
+        let mut rest = *self;
+        let mut used = Self::default();
+        for (i, a) in rest.letters.iter_mut().enumerate() {
+            let wanted = other.letters[i];
+            if *a < wanted {
+                let missing = wanted - *a;
+                rest.blanks = rest.blanks.checked_sub(missing)?;
+                used.letters[i] += missing;
+            }
+            *a = a.saturating_sub(wanted);
+        }
+
+        let mut wanted = other.blanks;
+        let from_blanks = wanted.min(rest.blanks);
+        rest.blanks -= from_blanks;
+        wanted -= from_blanks;
+        for (i, a) in rest.letters.iter_mut().enumerate() {
+            let taken = wanted.min(*a);
+            *a -= taken;
+            used.letters[i] += taken;
+            wanted -= taken;
         }
-        Some(Self { letters: sorted })
+        (wanted == 0).then_some((rest, used))
     }
 
     pub fn is_empty(&self) -> bool {
-        self.letters.iter().all(|&a| a == 0)
+        self.len() == 0
+    }
+
+    /// The number of letters, including blanks.
+    pub fn len(&self) -> usize {
+        self.letters.iter().map(|&a| a as usize).sum::<usize>() + self.blanks as usize
+    }
+
+    /// The number of blank tiles.
+    pub fn num_blanks(&self) -> usize {
+        self.blanks as usize
+    }
+
+    /// The number of each tile, in alphabet order.
+    pub(crate) fn counts(&self) -> &[u8; MAX_TILES] {
+        &self.letters
+    }
+
+    pub(crate) fn from_counts(letters: [u8; MAX_TILES]) -> Self {
+        Self { letters, blanks: 0 }
+    }
+
+    pub(crate) fn with_blanks(self, blanks: u8) -> Self {
+        Self { blanks, ..self }
     }
 }
 
@@ -50,6 +119,13 @@ impl TryFrom<&str> for SortedLetters {
     }
 }
 
+impl TryFrom<Option<SortedLetters>> for SortedLetters {
+    type Error = ();
+    fn try_from(letters: Option<SortedLetters>) -> Result<Self, Self::Error> {
+        letters.ok_or(())
+    }
+}
+
 impl TryFrom<Option<&str>> for SortedLetters {
     type Error = ();
     fn try_from(s: Option<&str>) -> Result<Self, Self::Error> {
@@ -61,13 +137,18 @@ impl TryFrom<Option<&str>> for SortedLetters {
     }
 }
 
+/// Spells the tiles as English letters.  Use `Alphabet::spell` for other
+/// alphabets.
 impl Display for SortedLetters {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (i, &count) in self.letters.iter().enumerate() {
+        for (&count, letter) in self.letters.iter().zip('a'..='z') {
             for _ in 0..count {
-                write!(f, "{}", (i as u8 + b'a') as char)?;
+                write!(f, "{letter}")?;
             }
         }
+        for _ in 0..self.blanks {
+            write!(f, "{BLANK}")?;
+        }
         Ok(())
     }
 }
@@ -79,7 +160,7 @@ mod tests {
 
     #[test]
     fn test_new() {
-        let mut expected = [0; 26];
+        let mut expected = [0; MAX_TILES];
         expected[0] = 2;
         expected[1] = 2;
         expected[2] = 1;
@@ -106,4 +187,36 @@ mod tests {
         let c = a.minus(&b).unwrap();
         check!(c == SortedLetters::from_word("een"));
     }
+
+    #[test]
+    fn test_blanks() {
+        let rack = SortedLetters::from_word("RETAIN?");
+        check!(rack.num_blanks() == 1);
+        check!(rack.len() == 7);
+        check!(rack.to_string() == "aeinrt?");
+        check!(SortedLetters::from_word("retains").is_subset(&rack));
+        check!(SortedLetters::from_word("retina").is_subset(&rack));
+        check!(!SortedLetters::from_word("certainly").is_subset(&rack));
+        check!(!SortedLetters::from_word("terrains").is_subset(&rack));
+        check!(SortedLetters::from_word("nastier").is_superset(&SortedLetters::from_word("ten?")));
+        check!(!SortedLetters::from_word("tent").is_superset(&SortedLetters::from_word("ten??")));
+    }
+
+    #[test]
+    fn test_minus_with_blanks() {
+        let rack = SortedLetters::from_word("cat??");
+        let (rest, used) = rack
+            .minus_with_blanks(&SortedLetters::from_word("tact"))
+            .unwrap();
+        check!(rest.to_string() == "?");
+        check!(used.to_string() == "t");
+        check!(rack.minus(&SortedLetters::from_word("attack")).is_none());
+
+        let word = SortedLetters::from_word("sings");
+        let (rest, used) = word
+            .minus_with_blanks(&SortedLetters::from_word("ing?"))
+            .unwrap();
+        check!(rest.to_string() == "s");
+        check!(used.to_string() == "s");
+    }
 }
@@ -0,0 +1,126 @@
+use super::{Alphabet, Entry};
+use std::ops::Range;
+
+/// The single words that can be spelled with the alphabet's tiles, split
+/// into tiles, for games such as Ghost that are played a tile at a time.
+/// Words are found by prefix or substring without looking at every one.
+#[derive(Debug)]
+pub struct TileWords {
+    // Words as tile indexes, sorted and without duplicates, so that the
+    // words with a given prefix are together.
+    words: Vec<Vec<u8>>,
+    // The number of letters in each word, which is more than the number
+    // of tiles if some tiles have two letters.
+    lens: Vec<usize>,
+    // Every suffix of every word, including the empty one, as a word index
+    // and tile offset, sorted by the tiles of the suffix.
+    suffixes: Vec<(u32, u16)>,
+}
+
+impl TileWords {
+    pub(super) fn new(entries: &[Entry], alphabet: &Alphabet) -> Self {
+        let mut words = entries
+            .iter()
+            .filter(|entry| entry.is_single_word() && entry.is_in_alphabet())
+            .filter_map(|entry| {
+                let tiles = alphabet.split(entry.text()).ok()?;
+                let tiles = tiles.into_iter().map(|idx| idx as u8).collect::<Vec<_>>();
+                Some((tiles, entry.len()))
+            })
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        words.dedup();
+        let (words, lens): (Vec<_>, Vec<_>) = words.into_iter().unzip();
+
+        let mut suffixes = words
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, word)| (0..=word.len()).map(move |offset| (idx as u32, offset as u16)))
+            .collect::<Vec<_>>();
+        suffixes.sort_unstable_by_key(|&(idx, offset)| &words[idx as usize][offset as usize..]);
+
+        Self {
+            words,
+            lens,
+            suffixes,
+        }
+    }
+
+    /// The tiles of a word.
+    pub fn tiles(&self, idx: usize) -> &[u8] {
+        &self.words[idx]
+    }
+
+    /// The number of letters in a word.
+    pub fn num_letters(&self, idx: usize) -> usize {
+        self.lens[idx]
+    }
+
+    /// Finds the word spelled by these tiles.
+    pub fn find(&self, tiles: &[u8]) -> Option<usize> {
+        self.words
+            .binary_search_by(|word| word.as_slice().cmp(tiles))
+            .ok()
+    }
+
+    /// Finds the words that start with `prefix`.
+    pub fn with_prefix(&self, prefix: &[u8]) -> Range<usize> {
+        let start = self.words.partition_point(|word| word.as_slice() < prefix);
+        let len = self.words[start..].partition_point(|word| word.starts_with(prefix));
+        start..start + len
+    }
+
+    /// Finds every place that `needle` appears in a word, including
+    /// overlapping ones, as a word index and tile offset.
+    pub fn occurrences(&self, needle: &[u8]) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let suffix = |&(idx, offset): &(u32, u16)| &self.words[idx as usize][offset as usize..];
+        let start = self.suffixes.partition_point(|s| suffix(s) < needle);
+        let len = self.suffixes[start..].partition_point(|s| suffix(s).starts_with(needle));
+        self.suffixes[start..start + len]
+            .iter()
+            .map(|&(idx, offset)| (idx as usize, offset as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexi::Lexicon;
+    use assert2::check;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_tile_words() {
+        let spanish = "spanish".parse().unwrap();
+        let words = ["llama", "calle", "callado", "ala", "sol y sombra"];
+        let lex = Lexicon::new(words, spanish, Default::default());
+        let words = lex.tile_words();
+        let tiles = |text: &str| {
+            let tiles = lex.alphabet().split(text).unwrap();
+            tiles.into_iter().map(|idx| idx as u8).collect_vec()
+        };
+        let spell = |idx: usize| {
+            let tiles = words.tiles(idx).iter();
+            tiles.map(|&t| lex.alphabet().tile(t as usize)).join("")
+        };
+
+        let found = words.with_prefix(&tiles("ca")).map(spell).collect_vec();
+        check!(found == ["callado", "calle"]);
+        check!(words.with_prefix(&tiles("sol")).is_empty());
+        let idx = words.find(&tiles("calle")).unwrap();
+        check!(words.num_letters(idx) == 5);
+        check!(words.tiles(idx).len() == 4);
+        check!(words.find(&tiles("cal")).is_none());
+
+        let found = words
+            .occurrences(&tiles("la"))
+            .map(|(idx, offset)| (spell(idx), offset))
+            .sorted()
+            .collect_vec();
+        // LL is a tile of its own, so "callado" doesn't have L A in it.
+        check!(found == [("ala".to_owned(), 1)]);
+        let found = words.occurrences(&tiles("a")).count();
+        check!(found == 7);
+        // Every word has the empty needle at every offset, ends included.
+        check!(words.occurrences(&[]).count() == 5 + 5 + 7 + 4);
+    }
+}
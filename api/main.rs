@@ -3,32 +3,58 @@
 
 mod anagrams;
 mod assets;
+mod budget;
+mod conundrum;
+mod ghost;
 mod grep;
 mod lexi;
+mod numbers;
+mod parallel;
 mod server;
 
-pub use self::anagrams::anagrams;
+pub use self::anagrams::{anagrams, AnagramOptions, AnagramScore};
+use self::lexi::Accents;
 use self::lexi::FilterBuilder;
 use self::lexi::Lexicon;
+use self::lexi::Lexicons;
 use self::lexi::Popularity;
-use self::lexi::{Filter, LengthRange, SortedLetters};
+use self::lexi::{
+    Alphabet, Check, Entry, Enumeration, Filter, LengthRange, LetterCount, LetterMask, Pattern,
+    Shape, Sort, SortKey, SortOrder, SortedLetters, BLANK,
+};
+use self::lexi::{LexiconConfig, LexiconSource, LoadError};
 use clap::ArgGroup;
+use clap::Args;
 use clap::CommandFactory;
 use clap::Parser;
 use lexi::solve_anagram;
 use owo_colors::OwoColorize;
-use std::io::stdout;
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
 use tracing_subscriber::fmt;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
 fn main() {
+    dotenv::dotenv().ok();
     install_tracing();
     let cmdline = Cmdline::parse();
+    if let Some(threads) = cmdline.threads {
+        parallel::set_max_threads(threads);
+    }
+    let lexicon = || cmdline.lexicon.load();
     match cmdline.command {
-        Subcommand::Server(opts) => server::start_sync(&opts),
-        Subcommand::Search(filter) => search(filter),
-        Subcommand::Grep(spec) => grep::search(&spec),
+        Subcommand::Server(opts) => server::start_sync(&opts, cmdline.lexicon.load_all()),
+        Subcommand::Search(filter) => search(*filter, &lexicon()),
+        Subcommand::Grep(spec) => grep::search(&spec, &lexicon()),
+        Subcommand::Anagram(opts) => anagrams::run(&opts, &lexicon()),
+        Subcommand::Ghost(opts) => ghost::run(&opts, &lexicon()),
+        Subcommand::Numbers(opts) => numbers::run(&opts),
+        Subcommand::Conundrum(opts) => conundrum::run(&opts, &lexicon()),
+        Subcommand::Index(IndexCommand::Build { output }) => build_index(&output, &lexicon()),
         Subcommand::Completions => gen_completions(),
     }
 }
@@ -45,47 +71,102 @@ pub fn install_tracing() {
     tracing_subscriber::registry().with(fmt).with(filter).init();
 }
 
-fn search(spec: FilterSpec) {
-    let filter = spec.to_filter();
+fn search(spec: FilterSpec, lexicon: &Lexicon) {
+    let (filter, rack, contains) = match spec.to_filter(lexicon) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            process::exit(1);
+        }
+    };
     if filter.is_empty() {
         println!("{}", "No filter specified".red());
         return;
     }
-    match spec.contains {
-        Some(contains) => search_contains(filter, contains),
-        None => search_regular(filter),
+    match (&spec.contains, contains) {
+        (Some(text), Some(contains)) => {
+            search_contains(filter, text, contains, &spec.sort, lexicon)
+        }
+        _ => search_regular(filter, rack, &spec.sort, lexicon),
     }
 }
 
-fn search_regular(filter: Filter) {
-    let lexicon = Lexicon::load();
+fn search_regular(filter: Filter, rack: Option<SortedLetters>, sort: &SortSpec, lexicon: &Lexicon) {
+    let entries = lexicon.par_matching_entries(&filter, parallel::max_threads());
+    sort.apply(entries.into_iter()).for_each(|entry| {
+        let blanks = rack
+            .and_then(|rack| entry.blanks_from(&rack))
+            .unwrap_or_default();
+        println!("{}", show_word(lexicon, entry, &blanks));
+    });
+}
 
-    lexicon
-        .entries()
-        .filter(|entry| filter.matches(entry))
+fn search_contains(
+    filter: Filter,
+    contains: &str,
+    sorted: SortedLetters,
+    sort: &SortSpec,
+    lexicon: &Lexicon,
+) {
+    sort.apply(lexicon.matching_entries(&filter))
         .for_each(|entry| {
-            let word = match lexicon.rate(entry) {
-                Popularity::Low => entry.word().to_owned(),
-                Popularity::Medium => entry.word().yellow().to_string(),
-                Popularity::High => entry.word().green().to_string(),
-            };
-            println!("{}", word);
+            let (remaining, blanks) = entry.letters().minus_with_blanks(&sorted).unwrap();
+            let remaining = highlight_popular_words(lexicon, remaining);
+            let contains = fill_blanks(lexicon, contains, &blanks);
+            let word = entry.phrase();
+            println!("{contains} + {remaining} = {word}");
         });
 }
 
-fn search_contains(filter: Filter, contains: String) {
-    let lexicon = Lexicon::load();
-    let sorted = SortedLetters::from_word(&contains);
+fn colorize(text: &str, rating: Popularity) -> String {
+    match rating {
+        Popularity::Low => text.to_owned(),
+        Popularity::Medium => text.yellow().to_string(),
+        Popularity::High => text.green().to_string(),
+    }
+}
 
-    lexicon
-        .entries()
-        .filter(|entry| filter.matches(entry))
-        .for_each(|entry| {
-            let remaining =
-                highlight_popular_words(&lexicon, entry.without_letters_in(&sorted).unwrap());
-            let word = entry.word();
-            println!("{contains} + {remaining} = {word}");
-        });
+/// Colours a word or phrase by popularity, with the letters that came from
+/// blanks underlined.
+fn show_word(lexicon: &Lexicon, entry: &Entry, blanks: &SortedLetters) -> String {
+    let rating = lexicon.rate(entry);
+    let phrase = entry.phrase();
+    if blanks.is_empty() {
+        return colorize(&phrase, rating);
+    }
+    let mut blanks = lexicon.alphabet().spell_tiles(blanks);
+    let mut shown = String::new();
+    let mut rest = phrase.as_ref();
+    while let Some(ch) = rest.chars().next() {
+        let lower = rest.to_lowercase();
+        let tile = blanks.iter().position(|tile| lower.starts_with(tile));
+        let len = match tile {
+            Some(idx) => blanks.remove(idx).chars().count(),
+            None => 1,
+        };
+        let end = rest
+            .char_indices()
+            .nth(len)
+            .map_or(rest.len(), |(end, _)| end);
+        let (text, after) = rest.split_at(end);
+        shown += &match tile {
+            Some(_) => text.underline().to_string(),
+            None => colorize(&ch.to_string(), rating),
+        };
+        rest = after;
+    }
+    shown
+}
+
+/// Replaces each blank in a rack with the tile it stood for, underlined.
+fn fill_blanks(lexicon: &Lexicon, rack: &str, blanks: &SortedLetters) -> String {
+    let mut tiles = lexicon.alphabet().spell_tiles(blanks).into_iter();
+    rack.chars()
+        .map(|ch| match (ch == BLANK).then(|| tiles.next()).flatten() {
+            Some(tile) => tile.underline().to_string(),
+            None => ch.to_string(),
+        })
+        .collect()
 }
 
 fn highlight_popular_words(lexicon: &Lexicon, letters: SortedLetters) -> String {
@@ -97,6 +178,21 @@ fn highlight_popular_words(lexicon: &Lexicon, letters: SortedLetters) -> String
     }
 }
 
+fn build_index(output: &Path, lexicon: &Lexicon) {
+    let result = File::create(output).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        lexicon.write_index(&mut out)?;
+        out.flush()
+    });
+    match result {
+        Ok(()) => println!("Wrote {} words to {}", lexicon.len(), output.display()),
+        Err(e) => {
+            eprintln!("{}", format!("{}: {e}", output.display()).red());
+            process::exit(1);
+        }
+    }
+}
+
 pub fn gen_completions() {
     use clap_complete::shells::Fish;
 
@@ -106,67 +202,478 @@ pub fn gen_completions() {
 
 #[derive(Debug, Parser)]
 struct Cmdline {
+    #[clap(flatten)]
+    lexicon: LexiconOpts,
+
+    /// The most threads that each search may use.  Defaults to one per
+    /// core.
+    #[clap(long, global = true, env = "WF_THREADS")]
+    threads: Option<usize>,
+
     #[clap(subcommand)]
     command: Subcommand,
 }
 
+// Where to load the lexicon from.  Command line flags take precedence over
+// environment variables (which may be set in `.env`), which take precedence
+// over the config file.  This is a plain comment because clap would use a
+// doc comment as the description of the whole program.
+#[derive(Debug, clap::Args)]
+struct LexiconOpts {
+    /// Name of the lexicon to use, from the config file.
+    #[clap(long, global = true, env = "WF_LEXICON")]
+    lexicon: Option<String>,
+
+    /// Word list, one word per line.
+    #[clap(long, global = true, env = "WF_WORDS")]
+    words: Option<PathBuf>,
+
+    /// Popular words, most popular first, optionally followed by a tab and
+    /// a frequency count.
+    #[clap(long, global = true, env = "WF_POPULAR")]
+    popular: Option<PathBuf>,
+
+    /// Precompiled index, made by `wf index build`.
+    #[clap(long, global = true, env = "WF_INDEX")]
+    index: Option<PathBuf>,
+
+    /// Config file listing the available lexicons.
+    #[clap(long, global = true, env = "WF_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Words ranked below this are rated as very popular.
+    #[clap(long, global = true, env = "WF_POPULAR_THRESHOLD")]
+    popular_threshold: Option<usize>,
+
+    /// The best anagram of some letters is very popular if it is ranked
+    /// below this.
+    #[clap(long, global = true, env = "WF_ANAGRAM_THRESHOLD")]
+    anagram_threshold: Option<usize>,
+
+    /// Whether accented letters count as the plain letters they're based
+    /// on, or as letters in their own right.
+    #[clap(long, global = true, value_enum, env = "WF_ACCENTS")]
+    accents: Option<Accents>,
+
+    /// The tiles that words are made from: english, spanish, welsh, or a
+    /// list of tiles such as "a b c ch d".
+    #[clap(long, global = true, env = "WF_ALPHABET")]
+    alphabet: Option<Alphabet>,
+}
+
+impl LexiconOpts {
+    fn config(&self) -> Result<LexiconConfig, LoadError> {
+        let config = match &self.config {
+            Some(path) => LexiconConfig::from_config_file(path)?,
+            None => LexiconConfig::default(),
+        };
+        Ok(config.with_default_source(LexiconSource {
+            words: self.words.clone(),
+            popular: self.popular.clone(),
+            index: self.index.clone(),
+            popular_threshold: self.popular_threshold,
+            anagram_threshold: self.anagram_threshold,
+            accents: self.accents,
+            alphabet: self.alphabet.clone(),
+        }))
+    }
+
+    /// Loads the chosen lexicon, or exits with an error message.
+    fn load(&self) -> Lexicon<'static> {
+        or_exit(
+            self.config()
+                .and_then(|config| config.load(self.lexicon.as_deref())),
+        )
+    }
+
+    /// Loads every lexicon in the config, or exits with an error message.
+    fn load_all(&self) -> Lexicons {
+        or_exit(self.config().and_then(|config| config.load_all()))
+    }
+}
+
+fn or_exit<T>(result: Result<T, LoadError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            process::exit(1);
+        }
+    }
+}
+
 #[derive(Debug, clap::Subcommand)]
 enum Subcommand {
     Server(ServerOpts),
-    Search(FilterSpec),
+    Search(Box<FilterSpec>),
     Grep(GrepSpec),
+    Anagram(AnagramOpts),
+    Ghost(GhostOpts),
+    Numbers(NumbersOpts),
+    Conundrum(ConundrumOpts),
+    #[clap(subcommand)]
+    Index(IndexCommand),
     Completions,
 }
 
+/// Precompiled lexicon indexes, which load faster than word lists.
+#[derive(Debug, clap::Subcommand)]
+enum IndexCommand {
+    /// Writes the selected lexicon to an index file.
+    Build {
+        #[clap(long, short)]
+        output: PathBuf,
+    },
+}
+
 #[derive(Debug, Parser)]
 pub struct ServerOpts {
     #[clap(short, long)]
     addr: Option<String>,
 }
 
+/// Lists the words that match a filter.
 #[derive(Debug, Parser, Default)]
 #[clap(group(
             ArgGroup::new("filter")
                 .required(true)
-                .args(&["contains", "contained"]),
+                .multiple(true)
+                .args(&[
+                    "contains",
+                    "contained",
+                    "pattern",
+                    "query",
+                    "starts_with",
+                    "ends_with",
+                    "substring",
+                    "counts",
+                    "enumeration",
+                    "vowels",
+                    "consonants",
+                    "shape",
+                    "min_popularity",
+                    "max_rank",
+                ]),
         ))]
 pub struct FilterSpec {
+    #[clap(flatten)]
+    limits: WordLimits,
+
+    #[clap(long)]
+    contains: Option<String>,
+
+    #[clap(long)]
+    contained: Option<String>,
+
+    /// Crossword pattern such as `C?T??E` or `.A.E`, where `?` or `.` is
+    /// any letter and `[AEIOU]` is any of those letters.
+    #[clap(long, short)]
+    pattern: Option<Pattern>,
+
+    /// Query such as `len:7 (has:q OR has:z) NOT pattern:??????s`.  Terms
+    /// are len, has, from, include, exclude, pattern, starts, ends, sub,
+    /// count, enum, vowels, consonants, shape, yvowels, ycons, yshape,
+    /// popular, rank and oneword.
+    #[clap(long, short)]
+    query: Option<String>,
+
+    /// Only words that start with this text.
+    #[clap(long)]
+    starts_with: Option<String>,
+
+    /// Only words that end with this text.
+    #[clap(long)]
+    ends_with: Option<String>,
+
+    /// Only words with this text somewhere in them.
+    #[clap(long)]
+    substring: Option<String>,
+
+    /// How many times a letter may appear: `e=2` for exactly two Es, `e=2+`
+    /// for at least two, or `e=1-3` for one to three.  May be repeated.
+    #[clap(long = "count", short = 'c')]
+    counts: Vec<LetterCount>,
+
+    /// Only phrases with this many letters in each word, such as `(4,5)`
+    /// or `3-3`.
+    #[clap(long)]
+    enumeration: Option<Enumeration>,
+
+    /// Number of vowels, such as `2` or `1-3`.
+    #[clap(long)]
+    vowels: Option<LengthRange>,
+
+    /// Number of consonants, such as `5` or `4-6`.
+    #[clap(long)]
+    consonants: Option<LengthRange>,
+
+    /// Vowel/consonant shape such as `CVCCVC`, where `?` is either.
+    #[clap(long)]
+    shape: Option<Shape>,
+
+    /// Count Y as a vowel for --vowels, --consonants and --shape.
+    #[clap(long)]
+    y_vowel: bool,
+
+    /// Only words rated at least this popular: low, medium or high.
+    #[clap(long)]
+    min_popularity: Option<Popularity>,
+
+    /// Only words ranked this high or higher in the popularity list,
+    /// counting from 0.
+    #[clap(long)]
+    max_rank: Option<usize>,
+
+    #[clap(flatten)]
+    sort: SortSpec,
+}
+
+impl FilterSpec {
+    /// Builds the filter, along with the letters for --contained and
+    /// --contains, which are read the way the lexicon reads its words.
+    #[allow(clippy::type_complexity)]
+    fn to_filter(
+        &self,
+        lexicon: &Lexicon,
+    ) -> Result<(Filter, Option<SortedLetters>, Option<SortedLetters>), String> {
+        let letters = |text: &Option<String>| {
+            text.as_deref()
+                .map(|text| lexicon.parse_letters(text))
+                .transpose()
+        };
+        let rack = letters(&self.contained)?;
+        let contains = letters(&self.contains)?;
+        let query = self
+            .query
+            .as_deref()
+            .map(|query| Check::parse(query, lexicon.alphabet()))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let filter = self
+            .limits
+            .add_to(FilterBuilder::new(), lexicon)?
+            .contains(contains)
+            .contained(rack)
+            .pattern(self.pattern.clone())
+            .check(query)
+            .prefix(self.starts_with.as_deref())
+            .suffix(self.ends_with.as_deref())
+            .substring(self.substring.as_deref())
+            .letter_counts(self.counts.iter().copied())
+            .enumeration(self.enumeration.clone())
+            .vowels(self.vowels, self.y_vowel)
+            .consonants(self.consonants, self.y_vowel)
+            .shape(self.shape, self.y_vowel)
+            .min_popularity(self.min_popularity)
+            .max_rank(self.max_rank)
+            .build();
+        Ok((filter, rack, contains))
+    }
+}
+
+/// Limits that `search` and `grep` both put on the words they list.
+#[derive(Debug, Args, Default)]
+pub struct WordLimits {
+    /// Number of letters, such as `5` or `4-6`.
     #[clap(long, short)]
     length: Option<LengthRange>,
 
+    /// Only words that use none of these letters.
     #[clap(long, short)]
     exclude_letters: Option<String>,
 
+    /// Only words that use each of these letters.
     #[clap(long, short)]
     include_letters: Option<String>,
 
+    /// Only single words, not phrases.
     #[clap(long, short = '1')]
     one_word: bool,
+}
 
-    #[clap(long)]
-    contains: Option<String>,
+impl WordLimits {
+    fn add_to(&self, filter: FilterBuilder, lexicon: &Lexicon) -> Result<FilterBuilder, String> {
+        let exclude = parse_mask(self.exclude_letters.as_deref(), lexicon)?;
+        let include = parse_mask(self.include_letters.as_deref(), lexicon)?;
+        Ok(filter
+            .length(self.length)
+            .exclude_letters(exclude)
+            .include_letters(include)
+            .single_word(self.one_word.then_some(true)))
+    }
+}
+
+/// Reads a set of letters, if there is one, the way the lexicon reads its
+/// words.
+pub fn parse_mask(text: Option<&str>, lexicon: &Lexicon) -> Result<Option<LetterMask>, String> {
+    text.map(|text| lexicon.parse_mask(text)).transpose()
+}
+
+/// How to order and page through the words that a search finds.
+#[derive(Debug, Args, Default)]
+pub struct SortSpec {
+    /// What to sort by.  Without this, words come out in lexicon order.
+    #[clap(long, value_enum)]
+    sort: Option<SortKey>,
 
+    /// asc for the order that --sort describes, or desc to reverse it.
+    #[clap(long, value_enum, default_value = "asc")]
+    order: SortOrder,
+
+    /// Show at most this many words.
     #[clap(long)]
-    contained: Option<String>,
+    limit: Option<usize>,
+
+    /// Skip this many words first.
+    #[clap(long, default_value_t = 0)]
+    offset: usize,
 }
 
-impl FilterSpec {
-    fn to_filter(&self) -> Filter {
-        FilterBuilder::new()
-            .length(self.length)
-            .exclude_letters(self.exclude_letters.as_deref())
-            .include_letters(self.include_letters.as_deref())
-            .single_word(self.one_word.then_some(true))
-            .contains(self.contains.as_deref())
-            .contained(self.contained.as_deref())
-            .build()
+impl SortSpec {
+    fn apply<'a>(
+        &self,
+        entries: impl Iterator<Item = &'a Entry<'a>>,
+    ) -> impl Iterator<Item = &'a Entry<'a>> {
+        let mut entries = entries.collect::<Vec<_>>();
+        Sort::new(self.sort, self.order).sort(&mut entries);
+        entries
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
     }
 }
 
+/// Lists the words that match a regex.
 #[derive(Debug, Parser)]
+// -i is --case-insensitive here, as it is for grep itself.
+#[clap(mut_arg("include_letters", |arg| arg.short(None)))]
 pub struct GrepSpec {
     #[clap(long, short = 'i')]
     case_insensitive: bool,
 
+    /// Match the whole word, as if the regex started with `^` and ended
+    /// with `$`.
+    #[clap(long, short)]
+    whole: bool,
+
+    #[clap(flatten)]
+    limits: WordLimits,
+
     regex: String,
+
+    #[clap(flatten)]
+    sort: SortSpec,
+}
+
+impl GrepSpec {
+    fn to_filter(&self, lexicon: &Lexicon) -> Result<Filter, String> {
+        let options = grep::RegexOptions {
+            case_insensitive: self.case_insensitive,
+            whole: self.whole,
+            size_limit: None,
+        };
+        let filter = FilterBuilder::new().regex(Some(grep::build_regex(&self.regex, options)?));
+        Ok(self.limits.add_to(filter, lexicon)?.build())
+    }
+}
+
+/// Finds the ways to split some letters into words.
+#[derive(Debug, Parser)]
+pub struct AnagramOpts {
+    /// Use phrases from the lexicon as well as single words.
+    #[clap(long)]
+    phrases: bool,
+
+    /// The most words in an anagram, counting --with words.
+    #[clap(long, short = 'n')]
+    max_words: Option<usize>,
+
+    /// The fewest letters in each word, apart from --with words.
+    #[clap(long, short, default_value_t = 1)]
+    min_length: usize,
+
+    /// A word that every anagram must contain.  May be repeated.
+    #[clap(long, short)]
+    with: Vec<String>,
+
+    /// A word that no anagram may contain.  May be repeated.
+    #[clap(long, short = 'x')]
+    without: Vec<String>,
+
+    /// Also show near misses that leave up to this many letters unused.
+    #[clap(long, default_value_t = 0)]
+    leftover: usize,
+
+    /// Show at most this many anagrams.
+    #[clap(long)]
+    limit: Option<usize>,
+
+    /// Show the best anagrams by this score first, finding only as many
+    /// as --limit needs.
+    #[clap(long, value_enum)]
+    score: Option<AnagramScore>,
+
+    /// Give up after trying this many words.
+    #[clap(long)]
+    max_nodes: Option<usize>,
+
+    /// Give up after this many seconds.
+    #[clap(long, value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+
+    letters: String,
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    s.parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("Invalid number of seconds: {s}"))
+}
+
+/// Works out who wins a game of Ghost from the given fragment.
+#[derive(Debug, Parser)]
+pub struct GhostOpts {
+    #[clap(long, short, default_value_t = 2)]
+    players: usize,
+
+    #[clap(long, short, default_value_t = ghost::DEFAULT_MIN_LEN)]
+    min_length: usize,
+
+    #[clap(long, short, value_enum)]
+    rules: Option<ghost::Rules>,
+
+    /// Give up after working out this many positions.
+    #[clap(long)]
+    max_nodes: Option<usize>,
+
+    /// Give up after this many seconds.
+    #[clap(long, value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+
+    fragment: String,
+}
+
+/// Solves a Countdown numbers round.
+#[derive(Debug, Parser)]
+pub struct NumbersOpts {
+    #[clap(long, short)]
+    target: u32,
+
+    numbers: Vec<u32>,
+}
+
+/// Generates Countdown conundrums, or solves one.
+#[derive(Debug, Parser)]
+pub struct ConundrumOpts {
+    /// How many conundrums to generate.
+    #[clap(long, short, default_value_t = 1)]
+    count: usize,
+
+    /// Seed for the random number generator.
+    #[clap(long, short)]
+    seed: Option<u64>,
+
+    /// A conundrum to solve, instead of generating new ones.
+    scramble: Option<String>,
 }
@@ -0,0 +1,330 @@
+use crate::NumbersOpts;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::process;
+use thiserror::Error;
+
+/// Number of tiles in a numbers round.
+const NUM_TILES: usize = 6;
+/// Each large number appears once in the pool.
+const LARGE: [u32; 4] = [25, 50, 75, 100];
+/// Each small number (1 to 10) appears twice in the pool.
+const MAX_SMALL: u32 = 10;
+/// Targets are always three digits.
+const MIN_TARGET: u32 = 100;
+const MAX_TARGET: u32 = 999;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum NumbersError {
+    #[error("Expected {NUM_TILES} numbers, got {0}")]
+    WrongCount(usize),
+    #[error("{0} is not a Countdown tile")]
+    NotATile(u32),
+    #[error("Too many {0}s")]
+    TooMany(u32),
+    #[error("Target must be between {MIN_TARGET} and {MAX_TARGET}, not {0}")]
+    BadTarget(u32),
+    #[error("Invalid number: {0:?}")]
+    Unparseable(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Op::Add => "+",
+            Op::Sub => "−",
+            Op::Mul => "×",
+            Op::Div => "÷",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+/// One arithmetic operation, such as `25 × 4 = 100`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Step {
+    pub lhs: u32,
+    pub op: Op,
+    pub rhs: u32,
+    pub result: u32,
+}
+
+impl Step {
+    /// Combines two numbers, larger first, returning None if the result
+    /// would not be a positive integer or the step would be pointless.
+    fn new(lhs: u32, op: Op, rhs: u32) -> Option<Self> {
+        let result = match op {
+            Op::Add => lhs + rhs,
+            Op::Sub => (lhs > rhs).then(|| lhs - rhs)?,
+            Op::Mul => (rhs > 1).then(|| lhs * rhs)?,
+            Op::Div => (rhs > 1 && lhs.is_multiple_of(rhs)).then(|| lhs / rhs)?,
+        };
+        Some(Self {
+            lhs,
+            op,
+            rhs,
+            result,
+        })
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {} = {}", self.lhs, self.op, self.rhs, self.result)
+    }
+}
+
+/// A way of reaching a number, one step at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub result: u32,
+    pub steps: Vec<Step>,
+}
+
+/// The best solutions for a numbers round.  If the target can't be reached
+/// exactly, these are the solutions that get closest.
+#[derive(Debug, Clone)]
+pub struct Solutions {
+    pub target: u32,
+    /// How far the solutions are from the target.
+    pub distance: u32,
+    /// All distinct solutions that use the fewest operations.
+    pub solutions: Vec<Solution>,
+}
+
+/// Checks that the numbers can be drawn from the standard Countdown tiles,
+/// and that the target is in range.
+pub fn validate(numbers: &[u32], target: u32) -> Result<(), NumbersError> {
+    if numbers.len() != NUM_TILES {
+        return Err(NumbersError::WrongCount(numbers.len()));
+    }
+    for (n, count) in numbers.iter().copied().counts() {
+        let available = if LARGE.contains(&n) {
+            1
+        } else if (1..=MAX_SMALL).contains(&n) {
+            2
+        } else {
+            return Err(NumbersError::NotATile(n));
+        };
+        if count > available {
+            return Err(NumbersError::TooMany(n));
+        }
+    }
+    if !(MIN_TARGET..=MAX_TARGET).contains(&target) {
+        return Err(NumbersError::BadTarget(target));
+    }
+    Ok(())
+}
+
+/// Parses a list of numbers separated by commas or spaces.
+pub fn parse_numbers(s: &str) -> Result<Vec<u32>, NumbersError> {
+    s.split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            part.parse()
+                .map_err(|_| NumbersError::Unparseable(part.to_owned()))
+        })
+        .collect()
+}
+
+/// Finds the solutions closest to the target, using the fewest operations.
+/// Every intermediate result must be a positive integer, and not every
+/// number has to be used.
+pub fn solve(numbers: &[u32], target: u32) -> Solutions {
+    let mut search = Search {
+        target,
+        best: None,
+        found: BTreeMap::new(),
+    };
+    let mut steps = Vec::with_capacity(numbers.len());
+    search.visit(numbers, &mut steps);
+
+    let (distance, _) = search.best.unwrap_or_default();
+    Solutions {
+        target,
+        distance,
+        solutions: search.found.into_values().collect(),
+    }
+}
+
+struct Search {
+    target: u32,
+    /// Distance from the target and number of steps of the best solutions
+    /// so far.
+    best: Option<(u32, usize)>,
+    /// Best solutions so far, keyed by their sorted steps to remove
+    /// duplicates that differ only in the order of independent steps.
+    found: BTreeMap<Vec<Step>, Solution>,
+}
+
+impl Search {
+    fn visit(&mut self, numbers: &[u32], steps: &mut Vec<Step>) {
+        for &n in numbers.iter() {
+            self.consider(n, steps);
+        }
+        if let Some((0, len)) = self.best {
+            // Nothing deeper can beat an exact solution that is no longer
+            // than this.
+            if steps.len() >= len {
+                return;
+            }
+        }
+
+        for i in 0..numbers.len() {
+            for j in (i + 1)..numbers.len() {
+                let (a, b) = (numbers[i].max(numbers[j]), numbers[i].min(numbers[j]));
+                for op in [Op::Add, Op::Sub, Op::Mul, Op::Div] {
+                    let Some(step) = Step::new(a, op, b) else {
+                        continue;
+                    };
+                    let mut rest = numbers
+                        .iter()
+                        .enumerate()
+                        .filter(|&(k, _)| k != i && k != j)
+                        .map(|(_, &n)| n)
+                        .collect_vec();
+                    rest.push(step.result);
+                    steps.push(step);
+                    self.visit(&rest, steps);
+                    steps.pop();
+                }
+            }
+        }
+    }
+
+    fn consider(&mut self, n: u32, steps: &[Step]) {
+        if let Some(last) = steps.last() {
+            if last.result != n {
+                // Only the latest result is new; anything else was
+                // considered when it was made.
+                return;
+            }
+        }
+        let steps = used_steps(n, steps);
+        let score = (n.abs_diff(self.target), steps.len());
+        match self.best {
+            Some(best) if score > best => return,
+            Some(best) if score < best => self.found.clear(),
+            _ => {}
+        }
+        self.best = Some(score);
+
+        let mut key = steps.clone();
+        key.sort();
+        self.found
+            .entry(key)
+            .or_insert(Solution { result: n, steps });
+    }
+}
+
+/// Returns just the steps that contribute to the final result, which is
+/// the result of the last step.
+fn used_steps(result: u32, steps: &[Step]) -> Vec<Step> {
+    let Some((last, earlier)) = steps.split_last() else {
+        return Vec::new();
+    };
+    debug_assert_eq!(last.result, result);
+    let mut wanted = vec![last.lhs, last.rhs];
+    let mut used = vec![*last];
+    for step in earlier.iter().rev() {
+        if let Some(pos) = wanted.iter().position(|&n| n == step.result) {
+            wanted.swap_remove(pos);
+            wanted.extend([step.lhs, step.rhs]);
+            used.push(*step);
+        }
+    }
+    used.reverse();
+    used
+}
+
+/// Runs the `wf numbers` subcommand.
+pub fn run(opts: &NumbersOpts) {
+    if let Err(e) = validate(&opts.numbers, opts.target) {
+        eprintln!("{}", e.red());
+        process::exit(1);
+    }
+
+    let solutions = solve(&opts.numbers, opts.target);
+    match solutions.distance {
+        0 => println!("{}: {}", solutions.target, "exact".green()),
+        d => println!("{}: {} away", solutions.target, d.yellow()),
+    }
+    for solution in &solutions.solutions {
+        if solution.steps.is_empty() {
+            println!("{}", solution.result);
+        } else {
+            println!("{}", solution.steps.iter().join(", "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    #[test]
+    fn test_validate() {
+        check!(validate(&[100, 75, 50, 25, 1, 1], 500) == Ok(()));
+        check!(validate(&[100, 75, 50, 25, 1], 500) == Err(NumbersError::WrongCount(5)));
+        check!(validate(&[100, 100, 50, 25, 1, 2], 500) == Err(NumbersError::TooMany(100)));
+        check!(validate(&[3, 3, 3, 25, 1, 2], 500) == Err(NumbersError::TooMany(3)));
+        check!(validate(&[11, 3, 4, 25, 1, 2], 500) == Err(NumbersError::NotATile(11)));
+        check!(validate(&[10, 3, 4, 25, 1, 2], 50) == Err(NumbersError::BadTarget(50)));
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        check!(parse_numbers("25, 50 1,2") == Ok(vec![25, 50, 1, 2]));
+        check!(parse_numbers("25,x") == Err(NumbersError::Unparseable("x".to_owned())));
+    }
+
+    #[test]
+    fn test_one_step() {
+        let solutions = solve(&[100, 4, 1, 1, 2, 3], 400);
+        check!(solutions.distance == 0);
+        check!(solutions.solutions.len() == 1);
+        check!(solutions.solutions[0].steps.iter().join(", ") == "100 × 4 = 400");
+    }
+
+    #[test]
+    fn test_no_steps() {
+        let solutions = solve(&[100, 4, 1, 1, 2, 3], 100);
+        check!(solutions.distance == 0);
+        check!(solutions.solutions[0].steps.is_empty());
+    }
+
+    #[test]
+    fn test_fewest_steps() {
+        let solutions = solve(&[75, 50, 2, 3, 8, 7], 812);
+        check!(solutions.distance == 0);
+        let shortest = solutions.solutions[0].steps.len();
+        check!(solutions
+            .solutions
+            .iter()
+            .all(|s| s.steps.len() == shortest));
+        for solution in &solutions.solutions {
+            check!(solution.result == 812);
+            check!(solution.steps.last().unwrap().result == 812);
+        }
+    }
+
+    #[test]
+    fn test_closest() {
+        // The most you can make from these is (2 + 1) × (2 + 1) × 3 × 3,
+        // so that is as close as we can get.
+        let solutions = solve(&[1, 1, 2, 2, 3, 3], 999);
+        check!(solutions.distance == 999 - 81);
+        check!(solutions.solutions.iter().all(|s| s.result == 81));
+    }
+}
@@ -0,0 +1,103 @@
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// The most threads a search may use, or 0 for one per core.
+static MAX_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Ranges shorter than this aren't worth a thread of their own.
+const MIN_CHUNK: usize = 1024;
+
+/// Each thread gets this many chunks on average, so that one slow chunk
+/// doesn't leave the other threads idle.
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// Caps the threads that each search may use, with 0 meaning one per core.
+pub fn set_max_threads(threads: usize) {
+    MAX_THREADS.store(threads, Ordering::Relaxed);
+}
+
+/// The number of threads that each search may use.
+pub fn max_threads() -> usize {
+    match MAX_THREADS.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        threads => threads,
+    }
+}
+
+/// Splits `0..len` into chunks of at least `min_chunk`, and runs `f` on
+/// each one using up to `threads` threads.  The results come back in the
+/// order of their chunks, whichever thread finished first.
+pub fn map_ranges<R: Send>(
+    len: usize,
+    threads: usize,
+    min_chunk: usize,
+    f: impl Fn(Range<usize>) -> R + Sync,
+) -> Vec<R> {
+    let threads = threads.min(len.div_ceil(min_chunk.max(1))).max(1);
+    if threads == 1 {
+        return vec![f(0..len)];
+    }
+    let num_chunks = (threads * CHUNKS_PER_THREAD).min(len);
+    let chunk = |i: usize| (len * i / num_chunks)..(len * (i + 1) / num_chunks);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(num_chunks));
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= num_chunks {
+                    break;
+                }
+                let result = f(chunk(i));
+                results.lock().unwrap().push((i, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_unstable_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Keeps the items that match, checking chunks of them on separate threads,
+/// in their original order.
+pub fn filter<T: Sync>(items: &[T], threads: usize, keep: impl Fn(&T) -> bool + Sync) -> Vec<&T> {
+    map_ranges(items.len(), threads, MIN_CHUNK, |range| {
+        items[range]
+            .iter()
+            .filter(|item| keep(item))
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    #[test]
+    fn test_map_ranges() {
+        for threads in [1, 2, 3, 8] {
+            let ranges = map_ranges(10, threads, 1, |range| range);
+            check!(ranges.first().unwrap().start == 0);
+            check!(ranges.last().unwrap().end == 10);
+            check!(ranges.windows(2).all(|w| w[0].end == w[1].start));
+        }
+        check!(map_ranges(10, 8, 100, |range| range.len()) == [10]);
+        check!(map_ranges(0, 8, 1, |range| range.len()) == [0]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let items = (0..10_000).collect::<Vec<_>>();
+        let expected = items.iter().filter(|&&n| n % 7 == 3).collect::<Vec<_>>();
+        for threads in [1, 4] {
+            check!(filter(&items, threads, |&n| n % 7 == 3) == expected);
+        }
+    }
+}
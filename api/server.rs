@@ -1,39 +1,54 @@
+mod conundrum;
+mod countdown;
+mod grep;
+mod numbers;
 mod search;
 mod types;
 
+use self::countdown::countdown;
+use self::numbers::numbers;
 use self::search::search;
+use self::types::LexiconInfo;
 use crate::assets::static_path;
-use crate::lexi::Lexicon;
+use crate::lexi::Lexicons;
 use crate::ServerOpts;
+use axum::http::StatusCode;
 use axum::Extension;
+use axum::Json;
 use axum::{routing::get, Router};
 use std::process;
 use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 
 // #[tokio::main]
-pub fn start_sync(opts: &ServerOpts) {
+pub fn start_sync(opts: &ServerOpts, lexicons: Lexicons) {
     ctrlc::set_handler(move || {
         process::exit(0);
     })
     .expect("Error setting Ctrl-C handler");
 
     let runtime = tokio::runtime::Runtime::new().unwrap();
-    runtime.block_on(start(opts));
+    runtime.block_on(start(opts, lexicons));
 }
 
-pub async fn start(opts: &ServerOpts) {
+pub async fn start(opts: &ServerOpts, lexicons: Lexicons) {
     let addr = match &opts.addr {
         Some(addr) => addr.to_owned(),
         None => "127.0.0.1:3000".to_owned(),
     };
     let addr = addr.parse::<std::net::SocketAddr>().unwrap();
-    let lexi = Arc::new(Lexicon::load());
+    let lexicons = Arc::new(lexicons);
 
     let app = Router::new()
         .route("/api/search", get(search))
+        .route("/api/grep", get(grep::grep))
+        .route("/api/lexicons", get(list_lexicons))
+        .route("/api/countdown", get(countdown))
+        .route("/api/numbers", get(numbers))
+        .route("/api/conundrum", get(conundrum::generate))
+        .route("/api/conundrum/solve", get(conundrum::solve))
         .fallback(get(static_path))
-        .layer(Extension(Arc::clone(&lexi)))
+        .layer(Extension(Arc::clone(&lexicons)))
         .layer(TraceLayer::new_for_http());
 
     println!("Listening on {}", addr);
@@ -44,3 +59,21 @@ pub async fn start(opts: &ServerOpts) {
         .await
         .unwrap();
 }
+
+async fn list_lexicons(Extension(lexicons): Extension<Arc<Lexicons>>) -> Json<Vec<LexiconInfo>> {
+    Json(
+        lexicons
+            .iter()
+            .map(|(name, lexi)| LexiconInfo {
+                name: name.to_owned(),
+                num_words: lexi.len(),
+                is_default: name == lexicons.default_name(),
+            })
+            .collect(),
+    )
+}
+
+/// Turns an error into a 400 response with the error message as its body.
+fn bad_request(e: impl ToString) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, e.to_string())
+}
@@ -0,0 +1,47 @@
+use super::search::blocking;
+use super::types::{ConundrumQuery, ConundrumResults, GeneratedConundrum, RatedWord, SolveQuery};
+use crate::conundrum;
+use crate::lexi::Lexicons;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Extension;
+use axum::Json;
+use itertools::Itertools;
+use std::sync::Arc;
+
+/// The most conundrums we'll generate in one request.
+const MAX_COUNT: usize = 100;
+
+pub async fn generate(
+    Query(query): Query<ConundrumQuery>,
+    Extension(lexicons): Extension<Arc<Lexicons>>,
+) -> Result<Json<ConundrumResults>, (StatusCode, String)> {
+    let count = query.count.unwrap_or(1).min(MAX_COUNT);
+    let mut rng = conundrum::rng(query.seed);
+    let conundrums = blocking(&lexicons, query.lexicon, move |lexi| {
+        Ok(conundrum::generate(lexi, count, &mut rng)
+            .into_iter()
+            .map(|c| GeneratedConundrum {
+                scramble: c.scramble,
+                answer: RatedWord::new(lexi, c.answer),
+            })
+            .collect_vec())
+    })
+    .await?;
+    Ok(Json(ConundrumResults { conundrums }))
+}
+
+pub async fn solve(
+    Query(query): Query<SolveQuery>,
+    Extension(lexicons): Extension<Arc<Lexicons>>,
+) -> Result<Json<Vec<RatedWord>>, (StatusCode, String)> {
+    let answers = blocking(&lexicons, query.lexicon, move |lexi| {
+        let answers = conundrum::solve(&query.term, lexi).map_err(super::bad_request)?;
+        Ok(answers
+            .into_iter()
+            .map(|entry| RatedWord::new(lexi, entry))
+            .collect_vec())
+    })
+    .await?;
+    Ok(Json(answers))
+}
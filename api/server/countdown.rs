@@ -0,0 +1,163 @@
+use super::types::{CountdownQuery, CountdownResults, RatedWord, WordGroup};
+use crate::lexi::FilterBuilder;
+use crate::lexi::Lexicon;
+use crate::lexi::Lexicons;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Extension;
+use axum::Json;
+use itertools::Itertools;
+use std::cmp::Reverse;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Number of letters in a Countdown selection.
+const NUM_LETTERS: usize = 9;
+/// Contestants may not pick more than this many vowels...
+const MAX_VOWELS: usize = 5;
+/// ...or more than this many consonants.
+const MAX_CONSONANTS: usize = 6;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LettersError {
+    #[error("Expected {NUM_LETTERS} letters, got {0}")]
+    WrongLength(usize),
+    #[error("Invalid character: {0:?}")]
+    InvalidChar(char),
+    #[error("Too many vowels: {0} (max {MAX_VOWELS})")]
+    TooManyVowels(usize),
+    #[error("Too many consonants: {0} (max {MAX_CONSONANTS})")]
+    TooManyConsonants(usize),
+}
+
+pub async fn countdown(
+    Query(query): Query<CountdownQuery>,
+    Extension(lexicons): Extension<Arc<Lexicons>>,
+) -> Result<Json<CountdownResults>, (StatusCode, String)> {
+    let lexi = lexicons
+        .get(query.lexicon.as_deref())
+        .map_err(super::bad_request)?;
+    let letters = validate_letters(&query.term).map_err(super::bad_request)?;
+    let limit = query.limit.unwrap_or(usize::MAX);
+    Ok(Json(solve(&letters, limit, lexi)))
+}
+
+/// Checks that `term` is a legal Countdown selection, and returns it in
+/// lower case.
+pub fn validate_letters(term: &str) -> Result<String, LettersError> {
+    let letters = term.trim().to_ascii_lowercase();
+    if let Some(ch) = letters.chars().find(|ch| !ch.is_ascii_lowercase()) {
+        return Err(LettersError::InvalidChar(ch));
+    }
+    if letters.len() != NUM_LETTERS {
+        return Err(LettersError::WrongLength(letters.len()));
+    }
+    let vowels = letters.chars().filter(|&ch| is_vowel(ch)).count();
+    let consonants = letters.len() - vowels;
+    if vowels > MAX_VOWELS {
+        return Err(LettersError::TooManyVowels(vowels));
+    }
+    if consonants > MAX_CONSONANTS {
+        return Err(LettersError::TooManyConsonants(consonants));
+    }
+    Ok(letters)
+}
+
+fn is_vowel(ch: char) -> bool {
+    matches!(ch, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// Countdown scores one point per letter, except that a nine-letter word
+/// scores double.
+pub fn score(len: usize) -> usize {
+    if len == NUM_LETTERS {
+        2 * len
+    } else {
+        len
+    }
+}
+
+fn solve(letters: &str, limit: usize, lexi: &Lexicon) -> CountdownResults {
+    let filter = FilterBuilder::new()
+        .contained(letters)
+        .single_word(true.into())
+        .build();
+    let mut words = lexi
+        .entries()
+        .filter(|entry| filter.matches(entry))
+        .map(|entry| (entry.len(), lexi.rate(entry), entry))
+        .collect_vec();
+    words.sort_by_key(|&(len, rating, entry)| {
+        (
+            Reverse(len),
+            Reverse((rating, entry.frequency())),
+            entry.word(),
+        )
+    });
+
+    let max_score = words.first().map(|&(len, ..)| score(len)).unwrap_or(0);
+    let full_monty = words
+        .iter()
+        .take_while(|&&(len, ..)| len == NUM_LETTERS)
+        .map(|&(.., entry)| RatedWord::new(lexi, entry))
+        .collect_vec();
+
+    words.truncate(limit);
+    let groups = words
+        .iter()
+        .group_by(|&&(len, ..)| len)
+        .into_iter()
+        .map(|(len, group)| WordGroup {
+            len,
+            words: group
+                .map(|&(.., entry)| RatedWord::new(lexi, entry))
+                .collect_vec(),
+        })
+        .collect_vec();
+
+    CountdownResults {
+        q: letters.to_owned(),
+        words: words
+            .iter()
+            .map(|&(.., entry)| entry.word().to_owned())
+            .collect_vec(),
+        groups,
+        max_score,
+        full_monty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::check;
+
+    #[test]
+    fn test_validate_letters() {
+        check!(validate_letters("RETAINSXP") == Ok("retainsxp".to_owned()));
+        check!(validate_letters("retain") == Err(LettersError::WrongLength(6)));
+        check!(validate_letters("retain?xp") == Err(LettersError::InvalidChar('?')));
+        check!(validate_letters("aeiouaest") == Err(LettersError::TooManyVowels(7)));
+        check!(validate_letters("strngthsa") == Err(LettersError::TooManyConsonants(8)));
+    }
+
+    #[test]
+    fn test_solve() {
+        let lexi =
+            Lexicon::from_iter(["tea", "eat", "ate", "steam", "mates", "meats", "xylophone"]);
+        let results = solve("steamxqzw", usize::MAX, &lexi);
+        check!(results.max_score == 5);
+        check!(results.full_monty.is_empty());
+        check!(results.words == ["mates", "meats", "steam", "ate", "eat", "tea"]);
+        check!(results.groups.len() == 2);
+    }
+
+    #[test]
+    fn test_full_monty() {
+        let lexi = Lexicon::from_iter(["earnings", "gin", "searching"]);
+        let results = solve("searching", 1, &lexi);
+        check!(results.max_score == 18);
+        check!(results.full_monty.len() == 1);
+        check!(results.words == ["searching"]);
+    }
+}
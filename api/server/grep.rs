@@ -0,0 +1,50 @@
+use super::search::{blocking, words_by_length, Page};
+use super::types::{CountedResults, GrepQuery};
+use crate::grep::{build_regex, RegexOptions};
+use crate::lexi::{FilterBuilder, LengthRange, Lexicons, Sort};
+use crate::parse_mask;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Extension;
+use axum::Json;
+use std::sync::Arc;
+
+/// The most memory a regex from a request may compile to.
+const REGEX_SIZE_LIMIT: usize = 256 * 1024;
+
+pub async fn grep(
+    Query(query): Query<GrepQuery>,
+    Extension(lexicons): Extension<Arc<Lexicons>>,
+) -> Result<Json<CountedResults>, (StatusCode, String)> {
+    let lexi = lexicons
+        .get(query.lexicon.as_deref())
+        .map_err(super::bad_request)?;
+    let options = RegexOptions {
+        case_insensitive: query.case_insensitive,
+        whole: query.whole,
+        size_limit: Some(REGEX_SIZE_LIMIT),
+    };
+    let re = build_regex(&query.term, options).map_err(super::bad_request)?;
+    let length = query
+        .length
+        .as_deref()
+        .map(str::parse::<LengthRange>)
+        .transpose()
+        .map_err(super::bad_request)?;
+    let filter = FilterBuilder::new()
+        .regex(Some(re))
+        .length(length)
+        .include_letters(parse_mask(query.include.as_deref(), lexi).map_err(super::bad_request)?)
+        .exclude_letters(parse_mask(query.exclude.as_deref(), lexi).map_err(super::bad_request)?)
+        .single_word(query.one_word.then_some(true))
+        .build();
+    let page = Page {
+        sort: query.sort.map(|key| Sort::new(Some(key), query.order)),
+        offset: query.offset.unwrap_or(0),
+        limit: query.limit.unwrap_or(usize::MAX),
+    };
+    blocking(&lexicons, query.lexicon, move |lexi| {
+        Ok(words_by_length(&filter, None, &page, lexi))
+    })
+    .await
+}
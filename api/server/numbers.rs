@@ -0,0 +1,31 @@
+use super::types::{NumbersQuery, NumbersResults};
+use crate::numbers;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::Json;
+use itertools::Itertools;
+
+pub async fn numbers(
+    Query(query): Query<NumbersQuery>,
+) -> Result<Json<NumbersResults>, (StatusCode, String)> {
+    let tiles = numbers::parse_numbers(&query.numbers).map_err(super::bad_request)?;
+    numbers::validate(&tiles, query.target).map_err(super::bad_request)?;
+
+    let target = query.target;
+    let solutions = super::search::run_blocking(move || Ok(numbers::solve(&tiles, target))).await?;
+    Ok(Json(NumbersResults {
+        target: solutions.target,
+        distance: solutions.distance,
+        solutions: solutions
+            .solutions
+            .into_iter()
+            .map(|solution| {
+                solution
+                    .steps
+                    .iter()
+                    .map(|step| step.to_string())
+                    .collect_vec()
+            })
+            .collect_vec(),
+    }))
+}
@@ -1,41 +1,225 @@
 use super::types::Decomposition;
+use super::types::GhostAnalysis;
 use super::types::{CountedResults, GameType, RatedWord, SearchQuery, SearchResults, WordGroup};
+use crate::anagrams::{best_anagrams, par_anagrams, AnagramError, AnagramScore};
+use crate::ghost;
+use crate::ghost::Rules;
+use crate::ghost::Side;
+use crate::lexi::Check;
 use crate::lexi::Entry;
+use crate::lexi::Filter;
 use crate::lexi::FilterBuilder;
+use crate::lexi::LengthRange;
+use crate::lexi::LetterCount;
 use crate::lexi::Lexicon;
+use crate::lexi::Lexicons;
+use crate::lexi::Pattern;
 use crate::lexi::Popularity;
+use crate::lexi::Sort;
+use crate::lexi::SortedLetters;
+use crate::parallel;
+use crate::AnagramOptions;
 use axum::extract::Query;
+use axum::http::StatusCode;
 use axum::Extension;
 use axum::Json;
 use itertools::Itertools;
+use std::borrow::Cow;
 use std::cmp::Reverse;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub async fn search(
     Query(query): Query<SearchQuery>,
-    Extension(lexi): Extension<Arc<Lexicon<'_>>>,
-) -> Json<CountedResults> {
-    let limit = query.limit.unwrap_or(usize::MAX);
+    Extension(lexicons): Extension<Arc<Lexicons>>,
+) -> Result<Json<CountedResults>, (StatusCode, String)> {
+    let lexi = lexicons
+        .get(query.lexicon.as_deref())
+        .map_err(super::bad_request)?;
+    let page = Page {
+        sort: query.sort.map(|key| Sort::new(Some(key), query.order)),
+        offset: query.offset.unwrap_or(0),
+        limit: query.limit.unwrap_or(usize::MAX),
+    };
+    let filter = extra_checks(&query).map_err(super::bad_request)?;
+    let name = query.lexicon.clone();
     match query.game_type {
-        GameType::Countdown | GameType::Connect => longest_subwords(&query.term, limit, lexi),
-        GameType::Anagram => anagram_search(&query.term, limit, lexi),
-        GameType::Ghost => todo!(),
+        GameType::Countdown | GameType::Connect => {
+            let rack = lexi
+                .parse_letters(&query.term)
+                .map_err(super::bad_request)?;
+            blocking(&lexicons, name, move |lexi| {
+                Ok(longest_subwords(rack, filter, &page, lexi))
+            })
+            .await
+        }
+        GameType::Anagram => {
+            let letters = lexi
+                .parse_letters(&query.term)
+                .map_err(super::bad_request)?;
+            let options = anagram_options(&query);
+            blocking(&lexicons, name, move |lexi| {
+                anagram_search(letters, &options, query.score, &page, lexi)
+                    .map_err(super::bad_request)
+            })
+            .await
+        }
+        GameType::Ghost => {
+            blocking(&lexicons, name, move |lexi| {
+                Ok(ghost_search(&query, &page, lexi))
+            })
+            .await
+        }
+        GameType::Crossword => {
+            let filter = match query.pattern {
+                Some(_) => filter,
+                None => filter.pattern(Some(query.term.parse().map_err(super::bad_request)?)),
+            };
+            let filter = filter.build();
+            blocking(&lexicons, name, move |lexi| {
+                Ok(words_by_length(&filter, None, &page, lexi))
+            })
+            .await
+        }
+        GameType::Query => {
+            let check = Check::parse(&query.term, lexi.alphabet()).map_err(super::bad_request)?;
+            let filter = filter.check(Some(check)).build();
+            blocking(&lexicons, name, move |lexi| {
+                Ok(words_by_length(&filter, None, &page, lexi))
+            })
+            .await
+        }
     }
 }
 
-fn longest_subwords(term: &str, limit: usize, lexi: Arc<Lexicon>) -> Json<CountedResults> {
-    let filter = FilterBuilder::new()
-        .contained(term)
-        .single_word(true.into())
-        .build();
-    let mut results = lexi
-        .entries()
-        .filter(|entry| filter.matches(entry))
-        .map(|entry| WordSummary::new(&lexi, entry))
-        .collect_vec();
+/// Runs a search on the blocking pool, with the lexicon that the request
+/// asked for.  Searches can take a while, or use several threads of their
+/// own, which would hold up the other requests on this thread.
+pub(super) async fn blocking<T: Send + 'static>(
+    lexicons: &Arc<Lexicons>,
+    name: Option<String>,
+    search: impl FnOnce(&Lexicon<'static>) -> Result<T, (StatusCode, String)> + Send + 'static,
+) -> Result<T, (StatusCode, String)> {
+    let lexicons = Arc::clone(lexicons);
+    run_blocking(move || {
+        let lexi = lexicons.get(name.as_deref()).map_err(super::bad_request)?;
+        search(lexi)
+    })
+    .await
+}
+
+/// Runs a search that doesn't need a lexicon on the blocking pool.
+pub(super) async fn run_blocking<T: Send + 'static>(
+    search: impl FnOnce() -> Result<T, (StatusCode, String)> + Send + 'static,
+) -> Result<T, (StatusCode, String)> {
+    tokio::task::spawn_blocking(search)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+}
+
+/// Which results to show, and in what order.
+#[derive(Clone, Copy)]
+pub(super) struct Page {
+    /// How to sort word lists.  Without this, they're grouped by length.
+    pub sort: Option<Sort>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Page {
+    fn apply<T>(&self, results: Vec<T>) -> Vec<T> {
+        results
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit)
+            .collect()
+    }
+}
+
+/// Builds the checks that narrow down the words in any search that lists
+/// them by length.
+fn extra_checks(query: &SearchQuery) -> Result<FilterBuilder, String> {
+    let pattern = query
+        .pattern
+        .as_deref()
+        .map(str::parse::<Pattern>)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let counts = match &query.count {
+        Some(counts) => LetterCount::parse_list(counts)?,
+        None => Vec::new(),
+    };
+    let enumeration = query.enumeration.as_deref().map(str::parse).transpose()?;
+    let range =
+        |range: &Option<String>| range.as_deref().map(str::parse::<LengthRange>).transpose();
+    let shape = query.shape.as_deref().map(str::parse).transpose()?;
+    let min_popularity = query
+        .min_popularity
+        .as_deref()
+        .map(str::parse)
+        .transpose()?;
+    Ok(FilterBuilder::new()
+        .pattern(pattern)
+        .prefix(query.starts_with.as_deref())
+        .suffix(query.ends_with.as_deref())
+        .substring(query.substring.as_deref())
+        .letter_counts(counts)
+        .enumeration(enumeration)
+        .vowels(range(&query.vowels)?, query.y_vowel)
+        .consonants(range(&query.consonants)?, query.y_vowel)
+        .shape(shape, query.y_vowel)
+        .min_popularity(min_popularity)
+        .max_rank(query.max_rank))
+}
+
+fn longest_subwords(
+    rack: SortedLetters,
+    filter: FilterBuilder,
+    page: &Page,
+    lexi: &Lexicon,
+) -> Json<CountedResults> {
+    let filter = filter.contained(rack).single_word(true.into()).build();
+    words_by_length(&filter, Some(rack), page, lexi)
+}
+
+/// Lists the words that match the filter, longest and most popular first,
+/// or in a flat list if the page has its own sort order.  If they were made
+/// from a rack, the letters that came from blanks are included.
+pub(super) fn words_by_length<'a>(
+    filter: &'a Filter,
+    rack: Option<SortedLetters>,
+    page: &Page,
+    lexi: &'a Lexicon,
+) -> Json<CountedResults> {
+    let summarize = |entry: &'a Entry| WordSummary {
+        blanks: rack
+            .and_then(|rack| entry.blanks_from(&rack))
+            .map(|blanks| lexi.alphabet().spell(&blanks))
+            .unwrap_or_default(),
+        ..WordSummary::new(lexi, entry)
+    };
+    let entries = lexi.par_matching_entries(filter, parallel::max_threads());
+    if let Some(sort) = page.sort {
+        let mut entries = entries;
+        sort.sort(&mut entries);
+        let num_total = entries.len();
+        let words = page
+            .apply(entries)
+            .into_iter()
+            .map(|entry| summarize(entry).into())
+            .collect_vec();
+        return Json(CountedResults {
+            num_total,
+            num_shown: words.len(),
+            truncated: false,
+            results: SearchResults::Words { words },
+        });
+    }
+
+    let mut results = entries.into_iter().map(summarize).collect_vec();
     let num_total = results.len();
-    results.sort_unstable_by_key(|s| Reverse((s.len, s.rating)));
-    let shown = results.into_iter().take(limit).collect_vec();
+    results.sort_unstable_by_key(|s| Reverse((s.len, s.rating, s.score)));
+    let shown = page.apply(results);
     let num_shown = shown.len();
     let groups = shown
         .into_iter()
@@ -46,67 +230,236 @@ fn longest_subwords(term: &str, limit: usize, lexi: Arc<Lexicon>) -> Json<Counte
     Json(CountedResults {
         num_total,
         num_shown,
+        truncated: false,
         results: SearchResults::WordsByLength { groups },
     })
 }
 
-fn anagram_search(term: &str, limit: usize, lexi: Arc<Lexicon>) -> Json<CountedResults> {
-    let results = crate::anagrams(term, &lexi)
-        .filter_map(|(words, residue)| residue.is_empty().then_some(words))
-        .collect_vec();
+/// The most words an Anagram search may try before giving up.
+const ANAGRAM_MAX_NODES: usize = 5_000_000;
+
+/// How long an Anagram search may run before giving up.
+const ANAGRAM_TIME_LIMIT: Duration = Duration::from_secs(2);
+
+/// The most anagrams that a search will collect and sort.
+const MAX_ANAGRAMS: usize = 10_000;
+
+/// The most letters that a near miss may leave unused.
+const MAX_LEFTOVER: usize = 2;
+
+/// The limits on an Anagram search.
+fn anagram_options(query: &SearchQuery) -> AnagramOptions {
+    let words = |list: &Option<String>| {
+        list.iter()
+            .flat_map(|list| list.split(','))
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(str::to_owned)
+            .collect_vec()
+    };
+    AnagramOptions {
+        phrases: query.phrases,
+        max_words: query.max_words,
+        min_word_len: query.min_word_len.unwrap_or(1),
+        required: words(&query.with),
+        banned: words(&query.without),
+        max_leftover: query.leftover.unwrap_or(0).min(MAX_LEFTOVER),
+        max_nodes: Some(ANAGRAM_MAX_NODES),
+        time_limit: Some(ANAGRAM_TIME_LIMIT),
+    }
+}
+
+/// Finds anagrams of the letters.  With a score, they are found best first,
+/// so only the ones up to the end of the page are needed.  Otherwise they
+/// are all found, and then sorted by the number of leftover letters and
+/// their least popular word.
+fn anagram_search(
+    letters: SortedLetters,
+    options: &AnagramOptions,
+    score: Option<AnagramScore>,
+    page: &Page,
+    lexi: &Lexicon,
+) -> Result<Json<CountedResults>, AnagramError> {
+    let (results, truncated) = match score {
+        Some(score) => {
+            let mut anagrams = best_anagrams(letters, options, score, lexi)?;
+            let wanted = page.offset.saturating_add(page.limit).min(MAX_ANAGRAMS);
+            let results = anagrams
+                .by_ref()
+                .filter(|(_, residue)| residue.len() <= options.max_leftover)
+                .take(wanted)
+                .collect_vec();
+            // The search stops at the end of the page, so unless it had
+            // nothing left to try, the total only counts what it found.
+            (results, !anagrams.is_exhausted() || anagrams.truncated())
+        }
+        None => {
+            let near_miss = |rest: &SortedLetters| rest.len() <= options.max_leftover;
+            let threads = parallel::max_threads();
+            let (mut results, truncated) =
+                par_anagrams(letters, options, lexi, threads, MAX_ANAGRAMS + 1, near_miss)?;
+            let more = results.len() > MAX_ANAGRAMS;
+            results.truncate(MAX_ANAGRAMS);
+            (results, more || truncated)
+        }
+    };
     let num_total = results.len();
     let mut results = results
         .iter()
-        .map(|entries| Decomposition {
-            words: entries
+        .map(|(words, residue)| Decomposition {
+            words: words
                 .iter()
-                .map(|entry| {
-                    let word = entry.word().into();
-                    let rating = lexi.rate(entry);
-                    RatedWord { word, rating }
-                })
+                .map(|w| RatedWord::new(lexi, w.entry).with_blanks(lexi, &w.blanks))
                 .collect_vec(),
+            remainder: lexi.alphabet().spell(residue),
         })
         .collect_vec();
 
     // Stable sort here, because there was already a vague ordering by
     // quality of the first word.
-    results.sort_by_key(|r| {
-        let most_unpopular = r
-            .words
-            .iter()
-            .map(|w| w.rating as usize)
-            .min()
-            .unwrap_or_default();
-        // let pop1 = r.words.get(0).map(|w| w.rating as usize).unwrap_or(0);
-        // let popularity = r.words.iter().map(|w| w.rating as usize).max().unwrap_or(0);
-        // let unpopularity = r.words.iter().map(|w| w.rating as usize).min().unwrap_or(5);
-        // let max_len = r.words.iter().map(|w| w.word.len()).max().unwrap_or(0);
-        // let min_len = r.words.iter().map(|w| w.word.len()).min().unwrap_or(20);
-        (Reverse(most_unpopular), r.words.len())
-    });
-
-    results.truncate(limit);
+    if score.is_none() {
+        results.sort_by_key(|r| {
+            let most_unpopular = r
+                .words
+                .iter()
+                .map(|w| w.rating as usize)
+                .min()
+                .unwrap_or_default();
+            // let pop1 = r.words.get(0).map(|w| w.rating as usize).unwrap_or(0);
+            // let popularity = r.words.iter().map(|w| w.rating as usize).max().unwrap_or(0);
+            // let unpopularity = r.words.iter().map(|w| w.rating as usize).min().unwrap_or(5);
+            // let max_len = r.words.iter().map(|w| w.word.len()).max().unwrap_or(0);
+            // let min_len = r.words.iter().map(|w| w.word.len()).min().unwrap_or(20);
+            (
+                r.remainder.chars().count(),
+                Reverse(most_unpopular),
+                r.words.len(),
+            )
+        });
+    }
 
-    Json(CountedResults {
+    let results = page.apply(results);
+
+    Ok(Json(CountedResults {
         num_total,
         num_shown: results.len(),
+        truncated,
         results: SearchResults::Anagrams { anagrams: results },
+    }))
+}
+
+fn ghost_search(query: &SearchQuery, page: &Page, lexi: &Lexicon) -> Json<CountedResults> {
+    let term = &query.term;
+    let mut results = ghost::plus_one(term, lexi)
+        .map(|entry| WordSummary::new(lexi, entry))
+        .collect_vec();
+    let num_total = results.len();
+    results.sort_by_key(|s| Reverse((s.rating, s.score)));
+    let results = page.apply(results);
+    let num_shown = results.len();
+    let groups = results
+        .into_iter()
+        .group_by(|s| s.len)
+        .into_iter()
+        .map(build_group)
+        .collect_vec();
+
+    let mut prepend = Vec::new();
+    let mut append = Vec::new();
+    if let Some(rules) = query.extend {
+        for ext in ghost::extensions(term, rules, lexi) {
+            let rated = RatedWord {
+                word: ext.fragment,
+                rating: ext.rating,
+                score: ext.score,
+                blanks: String::new(),
+            };
+            match ext.side {
+                Side::Front => prepend.push(rated),
+                Side::Back => append.push(rated),
+            }
+        }
+    }
+
+    let analysis = match query.players {
+        Some(players) => {
+            let min_len = query.min_len.unwrap_or(ghost::DEFAULT_MIN_LEN);
+            [Rules::Ghost, Rules::Superghost]
+                .into_iter()
+                .map(|rules| ghost_analysis(term, rules, players, min_len, lexi))
+                .collect_vec()
+        }
+        None => Vec::new(),
+    };
+
+    Json(CountedResults {
+        num_total,
+        num_shown,
+        truncated: false,
+        results: SearchResults::Ghost {
+            groups,
+            prepend,
+            append,
+            analysis,
+        },
     })
 }
 
+/// The most positions a Ghost analysis may work out before giving up.
+const GHOST_MAX_NODES: usize = 1_000_000;
+
+/// How long a Ghost analysis may run before giving up.
+const GHOST_TIME_LIMIT: Duration = Duration::from_secs(1);
+
+fn ghost_analysis(
+    term: &str,
+    rules: Rules,
+    players: usize,
+    min_len: usize,
+    lexi: &Lexicon,
+) -> GhostAnalysis {
+    let outcome = ghost::Solver::new(lexi, rules, players, min_len)
+        .map(|solver| solver.with_limits(Some(GHOST_MAX_NODES), Some(GHOST_TIME_LIMIT)))
+        .and_then(|mut solver| solver.solve(term));
+    match outcome {
+        Ok(outcome) => GhostAnalysis::Solved {
+            rules,
+            winning: outcome.is_winning(),
+            loser: outcome.loser,
+            winning_moves: outcome
+                .winning_moves()
+                .map(|m| m.fragment.clone())
+                .collect_vec(),
+            partial: outcome.partial,
+        },
+        Err(e) => GhostAnalysis::Invalid {
+            rules,
+            message: e.to_string(),
+        },
+    }
+}
+
 struct WordSummary<'a> {
-    word: &'a str,
+    word: Cow<'a, str>,
     len: usize,
     rating: Popularity,
+    score: Option<u64>,
+    blanks: String,
 }
 
 impl<'a> WordSummary<'a> {
     pub fn new(lexi: &'a Lexicon, entry: &'a Entry) -> Self {
-        let word = entry.word();
-        let len = word.len();
+        let word = entry.phrase();
+        let len = entry.len();
         let rating = lexi.rate(entry);
-        Self { word, len, rating }
+        let score = entry.frequency();
+        Self {
+            word,
+            len,
+            rating,
+            score,
+            blanks: String::new(),
+        }
     }
 }
 
@@ -116,12 +469,17 @@ where
 {
     WordGroup {
         len: word_length,
-        words: entries
-            .into_iter()
-            .map(|summary| RatedWord {
-                word: summary.word.to_owned(),
-                rating: summary.rating,
-            })
-            .collect_vec(),
+        words: entries.into_iter().map(RatedWord::from).collect_vec(),
+    }
+}
+
+impl From<WordSummary<'_>> for RatedWord {
+    fn from(summary: WordSummary) -> Self {
+        RatedWord {
+            word: summary.word.into_owned(),
+            rating: summary.rating,
+            score: summary.score,
+            blanks: summary.blanks,
+        }
     }
 }
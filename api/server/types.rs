@@ -1,4 +1,6 @@
-use crate::lexi::Popularity;
+use crate::anagrams::AnagramScore;
+use crate::ghost::Rules;
+use crate::lexi::{Entry, Lexicon, Popularity, SortKey, SortOrder, SortedLetters};
 use serde::{Deserialize, Serialize};
 
 /// A word game that we can suggest moves for.
@@ -13,6 +15,10 @@ pub enum GameType {
     Anagram,
     /// Find words that contain the given word plus one new letter
     Ghost,
+    /// Find words that fit a crossword pattern such as `C?T??E`
+    Crossword,
+    /// Find words that match a query such as `len:7 (has:q OR has:z)`
+    Query,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -22,6 +28,95 @@ pub struct SearchQuery {
     #[serde(rename = "goal")]
     pub game_type: GameType,
     pub limit: Option<usize>,
+    /// Which lexicon to search, if not the default.
+    pub lexicon: Option<String>,
+    /// For Ghost searches, also list the letters that can be added to
+    /// the fragment under these rules.
+    pub extend: Option<Rules>,
+    /// For Ghost searches, work out who wins with this many players.
+    pub players: Option<usize>,
+    /// For Ghost searches, the shortest word that ends the game.
+    pub min_len: Option<usize>,
+    /// Only show words that fit this crossword pattern.  For Crossword
+    /// searches, the search term is used if this is missing.
+    pub pattern: Option<String>,
+    /// Only show words that start with this text.
+    pub starts_with: Option<String>,
+    /// Only show words that end with this text.
+    pub ends_with: Option<String>,
+    /// Only show words with this text somewhere in them.
+    pub substring: Option<String>,
+    /// Only show words with these letter counts, such as `e=2+,s=1`.
+    pub count: Option<String>,
+    /// Only show phrases with this many letters in each word, such as
+    /// `4,5` or `3-3`.
+    pub enumeration: Option<String>,
+    /// For Anagram searches, also use the phrases in the lexicon, not just
+    /// single words.
+    #[serde(default)]
+    pub phrases: bool,
+    /// For Anagram searches, the most words in an anagram.
+    pub max_words: Option<usize>,
+    /// For Anagram searches, the fewest letters in each word.
+    pub min_word_len: Option<usize>,
+    /// For Anagram searches, words that every anagram must contain,
+    /// separated by commas.
+    pub with: Option<String>,
+    /// For Anagram searches, words that no anagram may contain, separated
+    /// by commas.
+    pub without: Option<String>,
+    /// For Anagram searches, also show near misses that leave up to this
+    /// many letters unused (at most 2).
+    pub leftover: Option<usize>,
+    /// For Anagram searches, list the best anagrams by this score first,
+    /// finding only as many as the page needs.
+    pub score: Option<AnagramScore>,
+    /// Only show words with this many vowels, such as `2` or `1-3`.
+    pub vowels: Option<String>,
+    /// Only show words with this many consonants.
+    pub consonants: Option<String>,
+    /// Only show words with this vowel/consonant shape, such as `CVCCVC`.
+    pub shape: Option<String>,
+    /// Count Y as a vowel for the vowel, consonant and shape filters.
+    #[serde(default)]
+    pub y_vowel: bool,
+    /// Only show words rated at least this popular: low, medium or high.
+    pub min_popularity: Option<String>,
+    /// Only show words ranked this high or higher in the popularity list.
+    pub max_rank: Option<usize>,
+    /// Sort word lists by this, as a flat list rather than grouped by
+    /// length.
+    pub sort: Option<SortKey>,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Skip this many results before the ones shown.
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrepQuery {
+    /// The regex to match words against.
+    #[serde(rename = "q")]
+    pub term: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Match the whole word rather than part of it.
+    #[serde(default)]
+    pub whole: bool,
+    pub lexicon: Option<String>,
+    /// Only show words with this many letters, such as `7` or `5-8`.
+    pub length: Option<String>,
+    /// Only show words with all of these letters.
+    pub include: Option<String>,
+    /// Only show words with none of these letters.
+    pub exclude: Option<String>,
+    #[serde(default)]
+    pub one_word: bool,
+    pub limit: Option<usize>,
+    pub sort: Option<SortKey>,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub offset: Option<usize>,
 }
 
 /// The result of a preview query or full search.
@@ -29,6 +124,9 @@ pub struct SearchQuery {
 pub struct CountedResults {
     pub num_total: usize,
     pub num_shown: usize,
+    /// True if the search gave up, or stopped at the end of the page,
+    /// before finding everything, so `num_total` only counts what it found.
+    pub truncated: bool,
     #[serde(flatten)]
     pub results: SearchResults,
 }
@@ -37,8 +135,50 @@ pub struct CountedResults {
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum SearchResults {
-    WordsByLength { groups: Vec<WordGroup> },
-    Anagrams { anagrams: Vec<Decomposition> },
+    WordsByLength {
+        groups: Vec<WordGroup>,
+    },
+    /// Words in the order that was asked for.
+    Words {
+        words: Vec<RatedWord>,
+    },
+    Anagrams {
+        anagrams: Vec<Decomposition>,
+    },
+    Ghost {
+        /// Words made by adding one letter to the fragment.
+        groups: Vec<WordGroup>,
+        /// Fragments made by adding a letter at the front.
+        prepend: Vec<RatedWord>,
+        /// Fragments made by adding a letter at the back.
+        append: Vec<RatedWord>,
+        /// Who wins from this fragment, under each set of rules.
+        analysis: Vec<GhostAnalysis>,
+    },
+}
+
+/// The game-theoretic value of a Ghost fragment.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status")]
+#[serde(rename_all = "snake_case")]
+pub enum GhostAnalysis {
+    Solved {
+        rules: Rules,
+        /// True if the player to move can make someone else lose.
+        winning: bool,
+        /// The player who loses under best play, counting the player to
+        /// move as 0.
+        loser: usize,
+        /// Fragments that the player to move can make to win.
+        winning_moves: Vec<String>,
+        /// True if the search gave up before working out every move, so
+        /// this may be wrong.
+        partial: bool,
+    },
+    Invalid {
+        rules: Rules,
+        message: String,
+    },
 }
 
 /// A group of words with the same length.
@@ -57,16 +197,122 @@ pub struct WordGroup {
 ///   MATES
 ///   ME + SAT
 ///   SET + AM
+///
+/// A near miss leaves some letters unused, such as ME + AT + S.
 #[derive(Debug, Clone, Serialize)]
 pub struct Decomposition {
     pub words: Vec<RatedWord>,
+    /// The letters left over, which is empty unless this is a near miss.
+    pub remainder: String,
 }
 
 /// A word, plus a rough estimate of its popularity.  The word is guaranteed
 /// to be in the lexicon, and the rating is a rough estimate of how popular
-/// the word is (1 = rare, 2 = common, 3 = very common)
+/// the word is (1 = rare, 2 = common, 3 = very common).  The score is the
+/// word's frequency, if known, for ranking words with the same rating.
+/// If the word was made from a rack with blank tiles, `blanks` holds the
+/// letters that the blanks stood for.
 #[derive(Debug, Clone, Serialize)]
 pub struct RatedWord {
     pub word: String,
     pub rating: Popularity,
+    pub score: Option<u64>,
+    pub blanks: String,
+}
+
+impl RatedWord {
+    pub fn new(lexi: &Lexicon, entry: &Entry) -> Self {
+        Self {
+            word: entry.phrase().into_owned(),
+            rating: lexi.rate(entry),
+            score: entry.frequency(),
+            blanks: String::new(),
+        }
+    }
+
+    pub fn with_blanks(self, lexi: &Lexicon, blanks: &SortedLetters) -> Self {
+        Self {
+            blanks: lexi.alphabet().spell(blanks),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CountdownQuery {
+    /// The nine letters on the board.
+    #[serde(rename = "q")]
+    pub term: String,
+    pub limit: Option<usize>,
+    pub lexicon: Option<String>,
+}
+
+/// Solutions to a Countdown letters round.
+#[derive(Debug, Clone, Serialize)]
+pub struct CountdownResults {
+    /// The letters that were searched, in lower case.
+    pub q: String,
+    /// The best words, longest first.
+    pub words: Vec<String>,
+    /// The same words, grouped by length.
+    pub groups: Vec<WordGroup>,
+    /// The highest score available from these letters.
+    pub max_score: usize,
+    /// Words that use all nine letters.
+    pub full_monty: Vec<RatedWord>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NumbersQuery {
+    /// The six tiles, separated by commas.
+    pub numbers: String,
+    pub target: u32,
+}
+
+/// Solutions to a Countdown numbers round.
+#[derive(Debug, Clone, Serialize)]
+pub struct NumbersResults {
+    pub target: u32,
+    /// How far the solutions are from the target; 0 for an exact answer.
+    pub distance: u32,
+    /// Each solution is a list of steps, such as "25 × 4 = 100".  An empty
+    /// list means that one of the tiles is the answer.
+    pub solutions: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConundrumQuery {
+    /// How many conundrums to generate.
+    pub count: Option<usize>,
+    /// Seed for the random number generator, to get repeatable results.
+    pub seed: Option<u64>,
+    pub lexicon: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConundrumResults {
+    /// Generated conundrums, most popular answer first.
+    pub conundrums: Vec<GeneratedConundrum>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedConundrum {
+    pub scramble: String,
+    pub answer: RatedWord,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SolveQuery {
+    #[serde(rename = "q")]
+    pub term: String,
+    pub lexicon: Option<String>,
+}
+
+/// A lexicon that searches can use.
+#[derive(Debug, Clone, Serialize)]
+pub struct LexiconInfo {
+    pub name: String,
+    pub num_words: usize,
+    /// True for the lexicon used when a search doesn't name one.
+    pub is_default: bool,
 }
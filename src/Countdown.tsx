@@ -12,6 +12,7 @@ import {
     initPool,
     reducePool,
 } from './pool'
+import { LengthGroup, RatedWord } from './SearchTypes'
 
 export function Countdown(): JSX.Element {
     const [searchParams, setSearchParams] = useSearchParams()
@@ -286,4 +287,7 @@ export interface CountdownQuery {
 interface CountdownResult {
     q: string
     words: string[]
+    groups: LengthGroup[]
+    max_score: number
+    full_monty: RatedWord[]
 }
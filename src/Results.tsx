@@ -7,6 +7,16 @@ export function Results(props: ResultsProps): JSX.Element | null {
     switch (data.type) {
         case 'words_by_length':
             return <WordsByLength data={data} form={form} preview={preview} />
+        case 'words':
+            return (
+                <Box>
+                    {data.words.map(({ word, rating, blanks }) => (
+                        <span key={word}>
+                            {colorizeWord(word, rating, blanks)}{' '}
+                        </span>
+                    ))}
+                </Box>
+            )
         case 'anagrams':
             return <Anagrams data={data} form={form} preview={preview} />
         default:
@@ -28,8 +38,8 @@ export function WordsByLength({
     const groups = data.groups.map(({ len, words }) => (
         <Box key={len}>
             {len}:
-            {words.map(({ word, rating }) => (
-                <span key={word}>{colorizeWord(word, rating)} </span>
+            {words.map(({ word, rating, blanks }) => (
+                <span key={word}>{colorizeWord(word, rating, blanks)} </span>
             ))}
         </Box>
     ))
@@ -68,6 +78,7 @@ function Anagrams({ data, form }: AnagramsProps): JSX.Element {
             <h2>Results for {searchTermFromInputString(form.input)}</h2>
             <p>
                 Showing {data.num_shown} of {data.num_total} results
+                {data.truncated && ' (the search stopped before finding them all)'}
             </p>
             <Box
                 sx={{
@@ -77,10 +88,14 @@ function Anagrams({ data, form }: AnagramsProps): JSX.Element {
             >
                 {data.anagrams.map(({ words, remainder }, i) => (
                     <Box key={i}>
-                        {words.map(({ word, rating }, idx) => (
+                        {words.map(({ word, rating, blanks }, idx) => (
                             <span key={word}>
                                 {idx > 0 && ' + '}
-                                {colorizeWord(word.toUpperCase(), rating)}
+                                {colorizeWord(
+                                    word.toUpperCase(),
+                                    rating,
+                                    blanks
+                                )}
                             </span>
                         ))}
                         {remainder && ` + ${remainder}`}
@@ -99,14 +114,31 @@ type AnagramsProps = {
     preview?: boolean
 }
 
-export const colorizeWord = (word: string, rating: WordRating) => {
+export const colorizeWord = (
+    word: string,
+    rating: WordRating,
+    blanks?: string
+) => {
     const styles = [
         { color: '#666' },
         {},
         { color: '#4caf50', fontWeight: 'bold' },
     ]
     const style = styles[rating - 1]
-    return <span style={style}>{word}</span>
+    if (!blanks) {
+        return <span style={style}>{word}</span>
+    }
+    // Underline the letters that came from blank tiles.
+    const remaining = blanks.split('')
+    const letters = word.split('').map((ch, i) => {
+        const idx = remaining.indexOf(ch.toLowerCase())
+        if (idx < 0) {
+            return ch
+        }
+        remaining.splice(idx, 1)
+        return <u key={i}>{ch}</u>
+    })
+    return <span style={style}>{letters}</span>
 }
 
 function searchTermFromInputString(input: string) {
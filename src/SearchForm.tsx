@@ -24,7 +24,8 @@ export function SearchForm({
     onSubmit,
 }: SearchFormProps) {
     const handleChange = (event: React.ChangeEvent<HTMLInputElement>) => {
-        const input = event.target.value.toUpperCase().replace(/[^A-Z]/g, '')
+        // Allow ? for a blank tile.
+        const input = event.target.value.toUpperCase().replace(/[^A-Z?]/g, '')
         onChange({ ...form, input })
     }
 
@@ -12,18 +12,44 @@ export type SearchResults = Counts & TypedResult
 export type Counts = {
     num_total: number
     num_shown: number
+    truncated?: boolean
 }
 
 export type TypedResult =
     | { type: 'words_by_length'; groups: LengthGroup[] }
+    | { type: 'words'; words: RatedWord[] }
     | { type: 'anagrams'; anagrams: AnagramResult[] }
+    | {
+          type: 'ghost'
+          groups: LengthGroup[]
+          prepend: RatedWord[]
+          append: RatedWord[]
+          analysis: GhostAnalysis[]
+      }
     | { type: 'empty' }
 
+export type GhostRules = 'ghost' | 'superghost'
+export type GhostAnalysis =
+    | {
+          status: 'solved'
+          rules: GhostRules
+          winning: boolean
+          loser: number
+          winning_moves: string[]
+          partial: boolean
+      }
+    | { status: 'invalid'; rules: GhostRules; message: string }
+
 /**
  * A group of words with the same length
  */
 export type LengthGroup = { len: number; words: RatedWord[] }
-export type RatedWord = { word: string; rating: Rating }
+export type RatedWord = {
+    word: string
+    rating: Rating
+    score?: number | null
+    blanks?: string
+}
 // 1 = unpopular word (but still in lexicon), 2 = popular word, 3 = very popular word
 export type Rating = 1 | 2 | 3
 